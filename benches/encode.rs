@@ -0,0 +1,62 @@
+//! Compares the time taken to produce raster output from a render.
+//!
+//! Run with `cargo bench --bench encode`.
+
+use mathjax::{MathJax, RasterOptions, RasterSize, Render};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 50;
+
+fn bench<T>(name: &str, mut f: impl FnMut() -> T) {
+    // Warm up
+    for _ in 0..3 {
+        black_box(f());
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let elapsed: Duration = start.elapsed() / ITERATIONS;
+    println!("{name:<40} {elapsed:>12.2?}");
+}
+
+/// How `Render::into_image` used to produce an image, by encoding the pixmap to PNG and decoding it again.
+fn png_round_trip(render: &Render, options: &RasterOptions) -> image::DynamicImage {
+    let pixmap = render.to_pixmap(options).unwrap();
+    image::load_from_memory_with_format(&pixmap.encode_png().unwrap(), image::ImageFormat::Png)
+        .unwrap()
+}
+
+fn main() {
+    let renderer = MathJax::new().unwrap();
+    let render = renderer
+        .render(r#"f(x) = \int_{-\infty}^\infty \hat f(\xi)\,e^{2 \pi i \xi x} \,d\xi"#)
+        .unwrap();
+    let options = RasterOptions {
+        size: RasterSize::Scale(10.0),
+        ..Default::default()
+    };
+
+    bench("to_pixmap", || render.to_pixmap(&options).unwrap());
+    bench("rasterize", || render.rasterize(&options).unwrap());
+    bench("rasterize (PNG round trip)", || {
+        png_round_trip(&render, &options)
+    });
+    bench("to_png_bytes", || render.to_png_bytes(&options).unwrap());
+    bench("to_png_bytes (via DynamicImage)", || {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        let image = render.rasterize(&options).unwrap();
+        image
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .unwrap();
+        bytes.into_inner()
+    });
+    bench("to_webp_bytes", || render.to_webp_bytes(&options).unwrap());
+    bench("to_jpeg_bytes", || {
+        render
+            .to_jpeg_bytes(&options, image::Rgb([255, 255, 255]), 90)
+            .unwrap()
+    });
+}
@@ -3,6 +3,9 @@
 fn main() {
     let renderer = mathjax::MathJax::new().unwrap();
     let mut res = renderer.render("f(x)=\\frac{1}{x}").unwrap();
-    res.set_color("green");
+    res.set_style(mathjax::Style {
+        color: Some("green".parse().unwrap()),
+        ..Default::default()
+    });
     res.into_image(10.0).unwrap().save("test.png").unwrap();
 }
@@ -3,6 +3,9 @@
 fn main() {
     let renderer = mathjax::MathJax::new().unwrap();
     let mut res = renderer.render("f(x)=\\frac{1}{x}").unwrap();
-    res.set_color("blue");
+    res.set_style(mathjax::Style {
+        color: Some("blue".parse().unwrap()),
+        ..Default::default()
+    });
     std::fs::write("test.svg", res.into_raw()).unwrap();
 }
@@ -0,0 +1,226 @@
+use crate::raster::into_rgba;
+use crate::{RasterError, RasterOptions, Render};
+
+/// Options which control how renders are packed into an [`Atlas`], see [`Atlas::pack`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasOptions {
+    /// The options each render is rasterized with.
+    ///
+    /// Renders should be drawn at a common scale, so this is best left as a [`RasterSize::FontSize`](crate::RasterSize::FontSize) or [`RasterSize::Scale`](crate::RasterSize::Scale).
+    pub raster: RasterOptions,
+    /// The maximum width (in pixels) of each page, defaults to `2048`.
+    pub max_width: u32,
+    /// The maximum height (in pixels) of each page, defaults to `2048`.
+    pub max_height: u32,
+    /// The transparent space (in pixels) left between sprites and around the edges of each page, defaults to `1`.
+    ///
+    /// This keeps neighbouring sprites from bleeding into each other when the texture is sampled with filtering.
+    pub padding: u32,
+}
+
+impl Default for AtlasOptions {
+    fn default() -> Self {
+        AtlasOptions {
+            raster: RasterOptions::default(),
+            max_width: 2048,
+            max_height: 2048,
+            padding: 1,
+        }
+    }
+}
+
+/// The location of a single render within an [`Atlas`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    /// The index of the page (in [`Atlas::pages`]) the render is drawn on.
+    pub page: usize,
+    /// The left edge of the render on the page (in pixels).
+    pub x: u32,
+    /// The top edge of the render on the page (in pixels).
+    pub y: u32,
+    /// The width of the render (in pixels).
+    pub width: u32,
+    /// The height of the render (in pixels).
+    pub height: u32,
+    /// The top left corner of the render, in texture coordinates (between `0.0` and `1.0`).
+    pub uv_min: [f32; 2],
+    /// The bottom right corner of the render, in texture coordinates (between `0.0` and `1.0`).
+    pub uv_max: [f32; 2],
+    /// The distance (in pixels) from the top of the render down to its baseline, this is where it should be aligned with surrounding text.
+    ///
+    /// This is `None` when the render has no metrics (e.g. when it has a tag).
+    pub baseline: Option<f32>,
+}
+
+/// Many renders rasterized and packed together into one or more images, for uploading as textures.
+///
+/// ```rust
+/// # fn main() {
+/// use mathjax::{Atlas, AtlasOptions, MathJax};
+///
+/// let renderer = MathJax::new().unwrap();
+/// let renders: Vec<_> = ["x^2", r#"\frac{1}{2}"#, r#"\sqrt{y}"#]
+///     .iter()
+///     .map(|expression| renderer.render(expression).unwrap())
+///     .collect();
+///
+/// let atlas = Atlas::pack(&renders, &AtlasOptions::default()).unwrap();
+/// assert_eq!(atlas.pages.len(), 1);
+/// assert_eq!(atlas.sprites.len(), 3);
+///
+/// // Each sprite matches the size the render would have on its own
+/// for (render, sprite) in renders.iter().zip(&atlas.sprites) {
+///     let options = AtlasOptions::default().raster;
+///     assert_eq!(render.raster_size(&options).unwrap(), (sprite.width, sprite.height));
+///     assert!(sprite.baseline.unwrap() < sprite.height as f32);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Atlas {
+    /// The packed images, each is at most [`AtlasOptions::max_width`] by [`AtlasOptions::max_height`].
+    pub pages: Vec<image::RgbaImage>,
+    /// The location of each render, in the same order as they were given.
+    pub sprites: Vec<Sprite>,
+}
+
+impl Atlas {
+    /// Rasterizes the given renders and packs them into as few pages as possible.
+    ///
+    /// Renders are sorted by height and placed in rows (shelves), a new page is started when a page is full.
+    /// Returns [`RasterError::InvalidSize`] if any render is too large to fit on a page on its own.
+    pub fn pack<'a>(
+        renders: impl IntoIterator<Item = &'a Render>,
+        options: &AtlasOptions,
+    ) -> Result<Atlas, RasterError> {
+        let padding = options.padding;
+        let prepared = renders
+            .into_iter()
+            .map(|render| render.prepare(&options.raster))
+            .collect::<Result<Vec<_>, _>>()?;
+        for render in &prepared {
+            let (width, height) = (render.size.width(), render.size.height());
+            if !fits(width, height, options) {
+                return Err(RasterError::InvalidSize {
+                    width: width as f64,
+                    height: height as f64,
+                });
+            }
+        }
+
+        // Tallest first, so each shelf wastes as little space as possible
+        let mut order: Vec<usize> = (0..prepared.len()).collect();
+        order.sort_by_key(|&index| std::cmp::Reverse(prepared[index].size.height()));
+
+        // The page, position and the size of each page used so far.
+        // Positions are added up in 64 bits, as a page can be as large as the largest `u32`
+        //  (every sprite fits on a page, so the results always fit back into 32 bits)
+        let (padding, max_width, max_height) = (
+            padding as u64,
+            options.max_width as u64,
+            options.max_height as u64,
+        );
+        let mut positions = vec![(0, 0, 0); prepared.len()];
+        let mut sizes: Vec<(u32, u32)> = Vec::new();
+        let (mut x, mut y, mut shelf) = (padding, padding, 0);
+        for index in order {
+            let (width, height) = (
+                prepared[index].size.width() as u64,
+                prepared[index].size.height() as u64,
+            );
+            if x + width + padding > max_width {
+                // Start a new shelf below the current one
+                x = padding;
+                y += shelf + padding;
+                shelf = 0;
+            }
+            if sizes.is_empty() || y + height + padding > max_height {
+                sizes.push((0, 0));
+                x = padding;
+                y = padding;
+                shelf = 0;
+            }
+            let page = sizes.len() - 1;
+            positions[index] = (page, x as u32, y as u32);
+            let size = &mut sizes[page];
+            size.0 = size.0.max((x + width + padding) as u32);
+            size.1 = size.1.max((y + height + padding) as u32);
+            x += width + padding;
+            shelf = shelf.max(height);
+        }
+
+        let mut pages: Vec<image::RgbaImage> = sizes
+            .iter()
+            .map(|&(width, height)| image::RgbaImage::new(width, height))
+            .collect();
+        let mut sprites = Vec::with_capacity(prepared.len());
+        for (render, &(page, x, y)) in prepared.iter().zip(&positions) {
+            let image = into_rgba(render.render()?)?;
+            image::imageops::replace(&mut pages[page], &image, x as i64, y as i64);
+
+            let (page_width, page_height) = (sizes[page].0 as f32, sizes[page].1 as f32);
+            let (width, height) = (image.width(), image.height());
+            sprites.push(Sprite {
+                page,
+                x,
+                y,
+                width,
+                height,
+                uv_min: [x as f32 / page_width, y as f32 / page_height],
+                uv_max: [
+                    (x + width) as f32 / page_width,
+                    (y + height) as f32 / page_height,
+                ],
+                baseline: render.baseline.map(|baseline| baseline as f32),
+            });
+        }
+
+        Ok(Atlas { pages, sprites })
+    }
+}
+
+/// Returns whether a render of the given size (in pixels) fits on a page, along with the padding around it.
+fn fits(width: u32, height: u32, options: &AtlasOptions) -> bool {
+    let padded = |size: u32| {
+        options
+            .padding
+            .checked_mul(2)
+            .and_then(|padding| size.checked_add(padding))
+    };
+    padded(width).is_some_and(|width| width <= options.max_width)
+        && padded(height).is_some_and(|height| height <= options.max_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_with_padding() {
+        let options = AtlasOptions::default();
+        assert!(fits(2046, 2046, &options));
+        assert!(!fits(2047, 10, &options));
+        assert!(!fits(10, 2047, &options));
+    }
+
+    #[test]
+    fn fits_without_overflow() {
+        let options = AtlasOptions {
+            max_width: u32::MAX,
+            max_height: u32::MAX,
+            padding: u32::MAX / 2 + 1,
+            ..Default::default()
+        };
+        assert!(!fits(1, 1, &options));
+
+        let options = AtlasOptions {
+            max_width: u32::MAX,
+            max_height: u32::MAX,
+            padding: 1,
+            ..Default::default()
+        };
+        assert!(fits(u32::MAX - 2, 1, &options));
+        assert!(!fits(u32::MAX - 1, 1, &options));
+        assert!(!fits(u32::MAX, 1, &options));
+    }
+}
@@ -0,0 +1,256 @@
+//! Renders [MathJax](https://www.mathjax.org/) expressions from the command line, this requires the `cli` feature.
+//!
+//! Expressions are taken from the arguments, a file (with `--file`), or otherwise standard input, with one expression per line
+//! (giving both expressions and `--file` is an error).
+//! For example, to render two expressions into `eq-1.png` and `eq-2.png` with red text:
+//! ```text
+//! mathjax-cli --format png --color red --output "eq-{n}.{ext}" "x^2" "\frac{1}{x}"
+//! ```
+//!
+//! The process exits with one of the following codes:
+//!  - `0` - Every expression was rendered.
+//!  - `1` - An expression could not be rendered (or with `--strict`, is not valid TeX).
+//!  - `2` - The arguments were invalid.
+//!  - `3` - The renderer could not be started (e.g. NodeJs is not installed).
+//!  - `4` - An input or output file could not be read or written.
+//!  - `5` - A render could not be converted into the output format.
+
+use clap::{Parser, ValueEnum};
+use mathjax::{Color, MathJax, RenderError, RenderOptions, Style};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Render MathJax expressions into SVG, PNG, MathML or PDF.
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    /// The expressions to render, these are read from `--file` (or standard input) if none are given.
+    expressions: Vec<String>,
+    /// A file to read expressions from, one per line (`-` for standard input).
+    #[arg(short, long, conflicts_with = "expressions")]
+    file: Option<PathBuf>,
+    /// The input format of the expressions.
+    #[arg(short, long, value_enum, default_value_t = Input::Tex)]
+    input: Input,
+    /// The output format.
+    #[arg(short = 't', long, value_enum, default_value_t = Format::Svg)]
+    format: Format,
+    /// Where to write each output, `{n}` is replaced with the (1-based) number of the expression and `{ext}` with the extension of the format.
+    ///
+    /// Outputs are written to standard output if this is not given.
+    #[arg(short, long)]
+    output: Option<String>,
+    /// How much to scale up PNG output.
+    #[arg(short, long, default_value_t = 10.0)]
+    scale: f32,
+    /// The font size (in points) of PDF output.
+    #[cfg(feature = "pdf")]
+    #[arg(long, default_value_t = 12.0)]
+    font_size: f64,
+    /// The text color, as any CSS color (e.g. `red` or `#ff0000`).
+    #[arg(short, long)]
+    color: Option<Color>,
+    /// Check each expression before rendering, failing on any TeX errors (rather than rendering them in the output).
+    #[arg(long)]
+    strict: bool,
+}
+
+/// The formats expressions can be written in, MathJax is only set up to read TeX.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Input {
+    Tex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Svg,
+    Png,
+    #[value(name = "mathml")]
+    MathMl,
+    Pdf,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Png => "png",
+            Format::MathMl => "mml",
+            Format::Pdf => "pdf",
+        }
+    }
+}
+
+/// A failure, along with the exit code it is reported with.
+enum Failure {
+    Render(RenderError),
+    Invalid(String),
+    Init(mathjax::InitError),
+    Io(std::io::Error),
+    Convert(mathjax::RasterError),
+    Usage(String),
+}
+
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Failure::Render(_) | Failure::Invalid(_) => 1,
+            Failure::Usage(_) => 2,
+            Failure::Init(_) => 3,
+            Failure::Io(_) => 4,
+            Failure::Convert(_) => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::Render(err) => err.fmt(f),
+            Failure::Init(err) => write!(f, "unable to start the renderer: {err}"),
+            Failure::Io(err) => err.fmt(f),
+            Failure::Convert(err) => err.fmt(f),
+            Failure::Invalid(message) | Failure::Usage(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<RenderError> for Failure {
+    fn from(err: RenderError) -> Self {
+        Failure::Render(err)
+    }
+}
+
+impl From<std::io::Error> for Failure {
+    fn from(err: std::io::Error) -> Self {
+        Failure::Io(err)
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("error: {failure}");
+            ExitCode::from(failure.exit_code())
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), Failure> {
+    // TeX is the only input format, so expressions are passed to MathJax as they are
+    let Input::Tex = args.input;
+    let expressions = if !args.expressions.is_empty() {
+        args.expressions.clone()
+    } else {
+        let mut input = String::new();
+        match args.file {
+            Some(ref path) if path.as_os_str() != "-" => {
+                input = std::fs::read_to_string(path)?;
+            }
+            _ => {
+                std::io::stdin().read_to_string(&mut input)?;
+            }
+        }
+        input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    if expressions.len() > 1
+        && args
+            .output
+            .as_ref()
+            .is_some_and(|output| !output.contains("{n}"))
+    {
+        return Err(Failure::Usage(String::from(
+            "the output path must contain `{n}` when rendering several expressions",
+        )));
+    }
+    if args.output.is_none()
+        && expressions.len() > 1
+        && matches!(args.format, Format::Png | Format::Pdf)
+    {
+        return Err(Failure::Usage(String::from(
+            "an output path is required when rendering several expressions into a binary format",
+        )));
+    }
+
+    let renderer = MathJax::new().map_err(Failure::Init)?;
+
+    if args.strict {
+        for (index, expression) in expressions.iter().enumerate() {
+            if let Some(diagnostic) = renderer.validate(expression)?.into_iter().next() {
+                return Err(Failure::Invalid(format!(
+                    "expression {} (at byte {}): {}",
+                    index + 1,
+                    diagnostic.span.start,
+                    diagnostic.message
+                )));
+            }
+        }
+    }
+
+    let outputs: Vec<Vec<u8>> = match args.format {
+        Format::MathMl => expressions
+            .iter()
+            .map(|expression| Ok(renderer.to_mml(expression)?.to_string().into_bytes()))
+            .collect::<Result<_, RenderError>>()?,
+        format => {
+            let style = Style {
+                color: args.color,
+                ..Default::default()
+            };
+            let batch = renderer.render_batch(&expressions, &RenderOptions::default())?;
+            let mut outputs = Vec::new();
+            for mut render in batch.into_renders() {
+                render.set_style(style);
+                outputs.push(convert(render, format, &args)?);
+            }
+            outputs
+        }
+    };
+
+    for (index, output) in outputs.iter().enumerate() {
+        match args.output {
+            Some(ref template) => {
+                let path = template
+                    .replace("{n}", &(index + 1).to_string())
+                    .replace("{ext}", args.format.extension());
+                std::fs::write(path, output)?;
+            }
+            None => {
+                let mut stdout = std::io::stdout().lock();
+                stdout.write_all(output)?;
+                if matches!(args.format, Format::Svg | Format::MathMl) {
+                    stdout.write_all(b"\n")?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts a render into the given output format.
+fn convert(render: mathjax::Render, format: Format, args: &Args) -> Result<Vec<u8>, Failure> {
+    match format {
+        Format::Svg | Format::MathMl => Ok(render.into_raw().into_bytes()),
+        Format::Png => {
+            let options = mathjax::RasterOptions {
+                size: mathjax::RasterSize::Scale(args.scale),
+                ..Default::default()
+            };
+            render.to_png_bytes(&options).map_err(Failure::Convert)
+        }
+        #[cfg(feature = "pdf")]
+        Format::Pdf => render.to_pdf(args.font_size).map_err(Failure::Convert),
+        #[cfg(not(feature = "pdf"))]
+        Format::Pdf => Err(Failure::Usage(String::from(
+            "PDF output requires the `pdf` feature",
+        ))),
+    }
+}
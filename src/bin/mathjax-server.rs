@@ -0,0 +1,418 @@
+//! Serves [MathJax](https://www.mathjax.org/) renders over HTTP, this requires the `server` feature.
+//!
+//! ```text
+//! mathjax-server --address 127.0.0.1:8080
+//! curl "http://127.0.0.1:8080/svg?tex=x%5E2"
+//! ```
+//!
+//! The following endpoints are available, each only accepts `GET` (or `HEAD`) requests:
+//!  - `/svg?tex=...` - Renders the expression into an SVG image.
+//!  - `/png?tex=...` - Renders the expression into a PNG image.
+//!  - `/mathml?tex=...` - Converts the expression into MathML.
+//!  - `/health` - Responds with `200 OK` once the server is ready to render.
+//!  - `/metrics` - Reports request counts and render times in the Prometheus text format.
+//!
+//! The `svg` and `png` endpoints accept a `color` parameter (any CSS color),
+//! and the `png` endpoint accepts `size` (the font size in points, defaults to `12`) and `dpi` (defaults to `96`) parameters.
+//!
+//! Every render is sent with an `ETag` (a hash of the request and the version of this crate) and `Cache-Control` header,
+//! requests with a matching `If-None-Match` header are answered with `304 Not Modified` without rendering.
+//! Requests with an overly long URL are rejected with `414 URI Too Long`, and expressions over `--max-length` bytes with `413 Payload Too Large`.
+//!
+//! The process exits with `3` if a renderer could not be started, or `4` if the address could not be listened on.
+
+use clap::Parser;
+use mathjax::{Color, MathJax, RasterOptions, RasterSize, Style};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Serve MathJax renders over HTTP.
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    /// The address to listen on.
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    address: String,
+    /// The number of renderers to start, each handles one request at a time (defaults to the number of CPUs).
+    #[arg(short, long)]
+    workers: Option<usize>,
+    /// The maximum length (in bytes) of an expression.
+    #[arg(long, default_value_t = 4096)]
+    max_length: usize,
+    /// How long (in seconds) clients may cache a render for.
+    #[arg(long, default_value_t = 86400)]
+    max_age: u64,
+}
+
+/// The longest URL (in bytes) which is accepted, expressions are percent-encoded so this allows for the worst case.
+fn max_url_length(args: &Args) -> usize {
+    args.max_length * 3 + 256
+}
+
+/// The largest font size (in points) and resolution (in dots per inch) a PNG can be requested at.
+const MAX_FONT_SIZE: f32 = 256.0;
+const MAX_DPI: f32 = 1200.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Endpoint {
+    Svg,
+    Png,
+    MathMl,
+    Health,
+    Metrics,
+}
+
+impl Endpoint {
+    const ALL: [Endpoint; 5] = [
+        Endpoint::Svg,
+        Endpoint::Png,
+        Endpoint::MathMl,
+        Endpoint::Health,
+        Endpoint::Metrics,
+    ];
+
+    fn from_path(path: &str) -> Option<Self> {
+        Endpoint::ALL
+            .into_iter()
+            .find(|endpoint| path == format!("/{}", endpoint.name()))
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Endpoint::Svg => "svg",
+            Endpoint::Png => "png",
+            Endpoint::MathMl => "mathml",
+            Endpoint::Health => "health",
+            Endpoint::Metrics => "metrics",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Endpoint::Svg => "image/svg+xml",
+            Endpoint::Png => "image/png",
+            Endpoint::MathMl => "application/mathml+xml",
+            Endpoint::Health => "text/plain; charset=utf-8",
+            Endpoint::Metrics => "text/plain; version=0.0.4",
+        }
+    }
+}
+
+/// Counters reported by the `/metrics` endpoint.
+#[derive(Default)]
+struct Metrics {
+    /// The number of requests to each endpoint, indexed as [`Endpoint::ALL`].
+    requests: [AtomicU64; 5],
+    /// The number of responses with each class of status code (`1xx` to `5xx`).
+    responses: [AtomicU64; 5],
+    /// The number of requests answered with `304 Not Modified`.
+    not_modified: AtomicU64,
+    /// The total time spent rendering, in microseconds.
+    render_micros: AtomicU64,
+    /// The number of renders.
+    renders: AtomicU64,
+}
+
+impl Metrics {
+    fn count_response(&self, status: u16) {
+        self.responses[(status as usize / 100).clamp(1, 5) - 1].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn to_prometheus(&self) -> String {
+        let mut metrics = String::new();
+        metrics.push_str("# HELP mathjax_requests_total Requests received by endpoint.\n");
+        metrics.push_str("# TYPE mathjax_requests_total counter\n");
+        for (endpoint, count) in Endpoint::ALL.iter().zip(&self.requests) {
+            metrics.push_str(&format!(
+                "mathjax_requests_total{{endpoint=\"{}\"}} {}\n",
+                endpoint.name(),
+                count.load(Ordering::Relaxed)
+            ));
+        }
+        metrics.push_str("# HELP mathjax_responses_total Responses sent by status class.\n");
+        metrics.push_str("# TYPE mathjax_responses_total counter\n");
+        for (class, count) in self.responses.iter().enumerate() {
+            metrics.push_str(&format!(
+                "mathjax_responses_total{{status=\"{}xx\"}} {}\n",
+                class + 1,
+                count.load(Ordering::Relaxed)
+            ));
+        }
+        metrics.push_str(
+            "# HELP mathjax_not_modified_total Requests answered from the client's cache.\n",
+        );
+        metrics.push_str("# TYPE mathjax_not_modified_total counter\n");
+        metrics.push_str(&format!(
+            "mathjax_not_modified_total {}\n",
+            self.not_modified.load(Ordering::Relaxed)
+        ));
+        metrics.push_str("# HELP mathjax_render_seconds Time spent rendering expressions.\n");
+        metrics.push_str("# TYPE mathjax_render_seconds summary\n");
+        metrics.push_str(&format!(
+            "mathjax_render_seconds_sum {}\n",
+            self.render_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        ));
+        metrics.push_str(&format!(
+            "mathjax_render_seconds_count {}\n",
+            self.renders.load(Ordering::Relaxed)
+        ));
+        metrics
+    }
+}
+
+/// A response which hasn't been sent yet.
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+    /// The entity tag of a render, which may be cached.
+    etag: Option<String>,
+}
+
+impl Reply {
+    fn ok(endpoint: Endpoint, body: Vec<u8>) -> Self {
+        Reply {
+            status: 200,
+            content_type: endpoint.content_type(),
+            body,
+            etag: None,
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Reply {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: (message.into() + "\n").into_bytes(),
+            etag: None,
+        }
+    }
+}
+
+/// Returns the entity tag of a render, from everything it depends on.
+///
+/// This is the 64-bit FNV-1a hash of the parts, which (unlike the hashers of the standard library) is the same across Rust releases and platforms.
+fn etag(parts: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        // Each part is prefixed with its length, so the boundaries between them are part of the hash
+        for &byte in (part.len() as u64)
+            .to_le_bytes()
+            .iter()
+            .chain(part.as_bytes())
+        {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    format!("\"{hash:016x}\"")
+}
+
+fn main() -> std::process::ExitCode {
+    let args = Args::parse();
+    let workers = args.workers.unwrap_or_else(|| {
+        std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+    });
+
+    // Every renderer is started before listening, so failing to start one is reported straight away
+    let renderers = match (0..workers.max(1))
+        .map(|_| MathJax::new())
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(renderers) => renderers,
+        Err(err) => {
+            eprintln!("error: unable to start the renderer: {err}");
+            return std::process::ExitCode::from(3);
+        }
+    };
+    let server = match Server::http(&args.address) {
+        Ok(server) => Arc::new(server),
+        Err(err) => {
+            eprintln!("error: unable to listen on {}: {err}", args.address);
+            return std::process::ExitCode::from(4);
+        }
+    };
+    // The address actually listened on, e.g. the port chosen for `--address 127.0.0.1:0`
+    eprintln!("listening on http://{}", server.server_addr());
+
+    let args = Arc::new(args);
+    let metrics = Arc::new(Metrics::default());
+    let handles: Vec<_> = renderers
+        .into_iter()
+        .map(|renderer| {
+            let (server, args, metrics) = (server.clone(), args.clone(), metrics.clone());
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(&renderer, &args, &metrics, request);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        let _ = handle.join();
+    }
+
+    std::process::ExitCode::SUCCESS
+}
+
+/// Responds to a single request.
+fn handle(renderer: &MathJax, args: &Args, metrics: &Metrics, request: Request) {
+    let reply = reply(renderer, args, metrics, &request);
+
+    let header = |name: &str, value: &str| Header::from_bytes(name, value).unwrap();
+    let mut cache = Vec::new();
+    if let Some(etag) = &reply.etag {
+        cache.push(header("ETag", etag));
+        cache.push(header(
+            "Cache-Control",
+            &format!("public, max-age={}", args.max_age),
+        ));
+    }
+    metrics.count_response(reply.status);
+
+    if reply.status == 304 {
+        let mut response = Response::empty(304);
+        for header in cache {
+            response.add_header(header);
+        }
+        let _ = request.respond(response);
+        return;
+    }
+    let mut response = Response::from_data(reply.body)
+        .with_status_code(reply.status)
+        .with_header(header("Content-Type", reply.content_type));
+    for header in cache {
+        response.add_header(header);
+    }
+    let _ = request.respond(response);
+}
+
+/// Builds the response to a request.
+fn reply(renderer: &MathJax, args: &Args, metrics: &Metrics, request: &Request) -> Reply {
+    if request.url().len() > max_url_length(args) {
+        return Reply::error(414, "the request URL is too long");
+    }
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let Some(endpoint) = Endpoint::from_path(path) else {
+        return Reply::error(404, format!("unknown endpoint {path}"));
+    };
+    metrics.requests[Endpoint::ALL
+        .iter()
+        .position(|&other| other == endpoint)
+        .unwrap()]
+    .fetch_add(1, Ordering::Relaxed);
+    if !matches!(request.method(), Method::Get | Method::Head) {
+        return Reply::error(405, "only GET requests are supported");
+    }
+
+    let parameter = |name: &str| {
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let tex = match endpoint {
+        Endpoint::Health => return Reply::ok(endpoint, b"ok\n".to_vec()),
+        Endpoint::Metrics => return Reply::ok(endpoint, metrics.to_prometheus().into_bytes()),
+        _ => match parameter("tex") {
+            Some(tex) if tex.len() > args.max_length => {
+                return Reply::error(
+                    413,
+                    format!("the expression is longer than {} bytes", args.max_length),
+                )
+            }
+            Some(tex) => tex,
+            None => return Reply::error(400, "missing the `tex` parameter"),
+        },
+    };
+    let color = match parameter("color").map(|color| color.parse::<Color>()) {
+        Some(Ok(color)) => Some(color),
+        Some(Err(err)) => return Reply::error(400, err.to_string()),
+        None => None,
+    };
+    let number = |name: &str, default: f32, max: f32| match parameter(name) {
+        None => Ok(default),
+        Some(value) => match value.parse::<f32>() {
+            Ok(number) if number > 0.0 && number <= max => Ok(number),
+            _ => Err(Reply::error(
+                400,
+                format!("`{name}` must be a number between 0 and {max}"),
+            )),
+        },
+    };
+    let (points, dpi) = match (
+        number("size", 12.0, MAX_FONT_SIZE),
+        number("dpi", 96.0, MAX_DPI),
+    ) {
+        (Ok(points), Ok(dpi)) => (points, dpi),
+        (Err(reply), _) | (_, Err(reply)) => return reply,
+    };
+
+    // A render only depends on the request (and the version of this crate), so a cached copy is checked for before rendering
+    let color_name = color.map(|color| color.to_string()).unwrap_or_default();
+    let size = match endpoint {
+        Endpoint::Png => format!("{points}@{dpi}"),
+        _ => String::new(),
+    };
+    let etag = etag(&[
+        env!("CARGO_PKG_VERSION"),
+        endpoint.name(),
+        &tex,
+        &color_name,
+        &size,
+    ]);
+    let cached = request
+        .headers()
+        .iter()
+        .filter(|header| header.field.equiv("If-None-Match"))
+        .flat_map(|header| header.value.as_str().split(','))
+        .any(|tag| tag.trim() == etag || tag.trim() == "*");
+    if cached {
+        metrics.not_modified.fetch_add(1, Ordering::Relaxed);
+        return Reply {
+            status: 304,
+            content_type: endpoint.content_type(),
+            body: Vec::new(),
+            etag: Some(etag),
+        };
+    }
+
+    let start = Instant::now();
+    let body = match endpoint {
+        Endpoint::MathMl => renderer
+            .to_mml(&tex)
+            .map(|math| math.to_string().into_bytes())
+            .map_err(|err| Reply::error(500, err.to_string())),
+        _ => renderer
+            .render(&tex)
+            .map_err(|err| Reply::error(500, err.to_string()))
+            .and_then(|mut render| {
+                render.set_style(Style {
+                    color,
+                    ..Default::default()
+                });
+                match endpoint {
+                    Endpoint::Png => render
+                        .to_png_bytes(&RasterOptions {
+                            size: RasterSize::FontSize { points, dpi },
+                            ..Default::default()
+                        })
+                        .map_err(|err| Reply::error(500, err.to_string())),
+                    _ => Ok(render.into_raw().into_bytes()),
+                }
+            }),
+    };
+    metrics
+        .render_micros
+        .fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+    metrics.renders.fetch_add(1, Ordering::Relaxed);
+
+    match body {
+        Ok(body) => Reply {
+            etag: Some(etag),
+            ..Reply::ok(endpoint, body)
+        },
+        Err(reply) => reply,
+    }
+}
@@ -0,0 +1,83 @@
+/// A color with an alpha channel.
+///
+/// Any valid CSS [color](https://developer.mozilla.org/en-US/docs/Web/CSS/color_value) can be parsed into a color:
+/// ```rust
+/// use mathjax::Color;
+///
+/// let color: Color = "rgba(255, 0, 0, 0.4)".parse().unwrap();
+/// assert_eq!(color, Color::rgba(255, 0, 0, 102));
+/// assert_eq!("red".parse::<Color>().unwrap(), Color::rgb(255, 0, 0));
+/// assert!("not a color".parse::<Color>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    /// The red channel.
+    pub red: u8,
+    /// The green channel.
+    pub green: u8,
+    /// The blue channel.
+    pub blue: u8,
+    /// The alpha channel, `0` is fully transparent and `255` is fully opaque.
+    pub alpha: u8,
+}
+
+impl Color {
+    /// Opaque black.
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    /// Opaque white.
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    /// Fully transparent black.
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    /// Creates an opaque color from its red, green and blue channels.
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Color::rgba(red, green, blue, 255)
+    }
+
+    /// Creates a color from its red, green, blue and alpha channels.
+    pub const fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        Color {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// Returns the color without its alpha channel as a hex string (e.g. `#ff0000`).
+    pub(crate) fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+
+    /// Returns the alpha channel as an opacity between `0.0` and `1.0`.
+    pub(crate) fn opacity(&self) -> f64 {
+        self.alpha as f64 / 255.0
+    }
+}
+
+impl std::str::FromStr for Color {
+    type Err = crate::ColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let color: svgtypes::Color = s.parse().map_err(|_| crate::ColorError(s.to_string()))?;
+        Ok(Color::rgba(color.red, color.green, color.blue, color.alpha))
+    }
+}
+
+impl std::fmt::Display for Color {
+    /// Formats the color as a CSS color, this is a hex color if it is opaque and an `rgba(...)` color otherwise.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.alpha == 255 {
+            f.write_str(&self.hex())
+        } else {
+            write!(
+                f,
+                "rgba({}, {}, {}, {})",
+                self.red,
+                self.green,
+                self.blue,
+                crate::metrics::format_number(self.opacity())
+            )
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::ops::Range;
+
+/// A problem with a TeX expression, see [`MathJax::validate`](crate::MathJax::validate).
+///
+/// ```rust
+/// # fn main() {
+/// use mathjax::{DiagnosticKind, MathJax};
+///
+/// let renderer = MathJax::new().unwrap();
+/// let expression = r#"x^2 + \foo{y}"#;
+/// let diagnostics = renderer.validate(expression).unwrap();
+///
+/// assert_eq!(diagnostics[0].kind, DiagnosticKind::UndefinedMacro);
+/// assert_eq!(&expression[diagnostics[0].span.clone()], r#"\foo"#);
+/// assert!(renderer.validate(r#"x^2 + y"#).unwrap().is_empty());
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The type of problem.
+    pub kind: DiagnosticKind,
+    /// The MathJax identifier of the error, e.g. `UndefinedControlSequence`.
+    pub id: String,
+    /// A description of the problem, e.g. `Undefined control sequence \foo`.
+    pub message: String,
+    /// The location of the problem, as byte offsets into the expression.
+    ///
+    /// For undefined macros, unknown environments and missing packages this covers the macro (or the `\begin{...}` or `\require{...}`),
+    /// otherwise it is empty and at the position MathJax found the problem (e.g. the end of the expression, for a missing `}`).
+    pub span: Range<usize>,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic from a MathJax error, found at the given offset (in UTF-16 code units) into the expression.
+    pub(crate) fn new(expression: &str, id: String, message: String, offset: usize) -> Self {
+        let kind = DiagnosticKind::from_id(&id);
+
+        // Offsets from JavaScript count UTF-16 code units rather than bytes
+        let mut units = 0;
+        let end = expression
+            .char_indices()
+            .find_map(|(index, c)| {
+                units += c.len_utf16();
+                (units > offset).then_some(index)
+            })
+            .unwrap_or(expression.len());
+
+        // The error is found just after the macro (or environment name) has been read
+        let token = match kind {
+            DiagnosticKind::UndefinedMacro => message.rsplit(' ').next().map(str::to_string),
+            DiagnosticKind::UnknownEnvironment => message
+                .split('\'')
+                .nth(1)
+                .map(|name| format!("\\begin{{{name}}}")),
+            // The package is named as an extension, e.g. `[tex]/foo`
+            DiagnosticKind::MissingPackage => message.split('"').nth(1).map(|extension| {
+                let name = extension.rsplit('/').next().unwrap_or(extension);
+                format!("\\require{{{name}}}")
+            }),
+            _ => None,
+        };
+        let start = token
+            .filter(|token| expression[..end].ends_with(token.as_str()))
+            .map_or(end, |token| end - token.len());
+
+        Diagnostic {
+            kind,
+            id,
+            message,
+            span: start..end,
+        }
+    }
+}
+
+/// The type of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    /// A macro (control sequence) which isn't defined, e.g. `\foo`.
+    UndefinedMacro,
+    /// A `{` without a matching `}` (or the other way around).
+    UnbalancedBraces,
+    /// A `\begin{...}` without a matching `\end{...}` (or the other way around).
+    UnbalancedEnvironment,
+    /// An environment which isn't defined, e.g. `\begin{foo}`.
+    UnknownEnvironment,
+    /// A package which doesn't exist or couldn't be loaded.
+    MissingPackage,
+    /// Any other problem, see [`Diagnostic::id`].
+    Other,
+}
+
+impl DiagnosticKind {
+    /// Returns the type of a MathJax error with the given identifier.
+    fn from_id(id: &str) -> Self {
+        match id {
+            "UndefinedControlSequence" => DiagnosticKind::UndefinedMacro,
+            "MissingCloseBrace"
+            | "ExtraCloseMissingOpen"
+            | "ExtraOpenMissingClose"
+            | "ExtraCloseLooking" => DiagnosticKind::UnbalancedBraces,
+            "EnvBadEnd" | "EnvMissingEnd" => DiagnosticKind::UnbalancedEnvironment,
+            "UnknownEnv" => DiagnosticKind::UnknownEnvironment,
+            "BadPackageName" | "BadRequire" | "NotAPackage" => DiagnosticKind::MissingPackage,
+            _ => DiagnosticKind::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(expression: &str, id: &str, message: &str, offset: usize) -> Diagnostic {
+        Diagnostic::new(expression, id.to_string(), message.to_string(), offset)
+    }
+
+    #[test]
+    fn undefined_macro() {
+        let expression = r"x^2 + \foo{y}";
+        let found = diagnostic(
+            expression,
+            "UndefinedControlSequence",
+            r"Undefined control sequence \foo",
+            10,
+        );
+        assert_eq!(found.kind, DiagnosticKind::UndefinedMacro);
+        assert_eq!(&expression[found.span], r"\foo");
+    }
+
+    #[test]
+    fn unknown_environment() {
+        let expression = r"a \begin{foo} b \end{foo}";
+        let found = diagnostic(expression, "UnknownEnv", "Unknown environment 'foo'", 13);
+        assert_eq!(found.kind, DiagnosticKind::UnknownEnvironment);
+        assert_eq!(&expression[found.span], r"\begin{foo}");
+    }
+
+    #[test]
+    fn missing_package() {
+        let expression = r"\require{foo} x";
+        let found = diagnostic(
+            expression,
+            "BadRequire",
+            r#"Extension "[tex]/foo" is not allowed to be loaded"#,
+            13,
+        );
+        assert_eq!(found.kind, DiagnosticKind::MissingPackage);
+        assert_eq!(&expression[found.span], r"\require{foo}");
+
+        // An invalid name isn't part of the message
+        let found = diagnostic(
+            r"\require{a-b}",
+            "BadPackageName",
+            r"Argument for \require is not a valid package name",
+            13,
+        );
+        assert_eq!(found.span, 13..13);
+    }
+
+    #[test]
+    fn utf16_offsets() {
+        // `é` is one UTF-16 code unit (but two bytes), `𝑥` is two code units (and four bytes)
+        let expression = r"é𝑥 \foo";
+        let found = diagnostic(
+            expression,
+            "UndefinedControlSequence",
+            r"Undefined control sequence \foo",
+            8,
+        );
+        assert_eq!(found.span, 7..11);
+        assert_eq!(&expression[found.span], r"\foo");
+    }
+
+    #[test]
+    fn empty_span() {
+        // A missing brace is found at the end of the expression
+        let found = diagnostic("{x", "MissingCloseBrace", "Missing close brace", 2);
+        assert_eq!(found.kind, DiagnosticKind::UnbalancedBraces);
+        assert_eq!(found.span, 2..2);
+        // Offsets past the end are clamped to it
+        assert_eq!(diagnostic("{x", "Other", "", 100).span, 2..2);
+        // A token which doesn't match the expression isn't included
+        let found = diagnostic(
+            r"\bar",
+            "UndefinedControlSequence",
+            r"Undefined control sequence \foo",
+            4,
+        );
+        assert_eq!(found.span, 4..4);
+    }
+
+    #[test]
+    fn kinds() {
+        for (id, kind) in [
+            ("ExtraCloseMissingOpen", DiagnosticKind::UnbalancedBraces),
+            ("EnvMissingEnd", DiagnosticKind::UnbalancedEnvironment),
+            ("BadPackageName", DiagnosticKind::MissingPackage),
+            ("DoubleExponent", DiagnosticKind::Other),
+        ] {
+            assert_eq!(DiagnosticKind::from_id(id), kind);
+        }
+    }
+}
@@ -0,0 +1,535 @@
+use crate::RenderOptions;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// HTML elements whose contents are never searched for math (when [`DocumentOptions::skip_code`] is enabled).
+///
+/// These match the `skipHtmlTags` option of MathJax.
+pub(crate) const SKIP_TAGS: &[&str] = &[
+    "script",
+    "noscript",
+    "style",
+    "textarea",
+    "pre",
+    "code",
+    "annotation",
+    "annotation-xml",
+];
+
+/// Options which control how math is found in (and substituted into) a document, see [`MathJax::render_document`](crate::MathJax::render_document).
+///
+/// These mirror the options of the MathJax TeX input, as used by `tex2svg-page`:
+/// ```rust
+/// # fn main() {
+/// use mathjax::DocumentOptions;
+///
+/// let options = DocumentOptions::default();
+/// let document = r#"Let $x^2$ be \(y\), where $$x = \frac{1}{2}$$ costs \$5 (see `$code$`)."#;
+/// let math: Vec<_> = options.find_math(document).into_iter().map(|math| (math.tex, math.display)).collect();
+/// assert_eq!(math, [
+///     (String::from("x^2"), false),
+///     (String::from("y"), false),
+///     (String::from(r#"x = \frac{1}{2}"#), true),
+/// ]);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DocumentOptions {
+    /// The pairs of start and end delimiters of inline math, defaults to `$...$` and `\(...\)`.
+    pub inline_math: Vec<(String, String)>,
+    /// The pairs of start and end delimiters of display math, defaults to `$$...$$` and `\[...\]`.
+    pub display_math: Vec<(String, String)>,
+    /// Whether `\$` outside of math produces a literal dollar sign (rather than starting math), defaults to `true`.
+    pub process_escapes: bool,
+    /// Whether `\begin{...}...\end{...}` outside of delimiters is treated as display math, defaults to `true`.
+    pub process_environments: bool,
+    /// Whether code is skipped, defaults to `true`.
+    ///
+    /// This covers Markdown code spans and blocks (delimited by backticks), as well as the contents of HTML elements such as `<code>` and `<pre>`.
+    pub skip_code: bool,
+    /// Whether HTML character references in math (such as `&lt;` or `&#x3C;`) are decoded before it is rendered, defaults to `true`.
+    ///
+    /// This matches MathJax in a web page, where the math is found in the text of the page (after its references are decoded).
+    pub decode_entities: bool,
+    /// The class of HTML elements whose contents are skipped, defaults to `mathjax_ignore`.
+    ///
+    /// This is a regular expression, matched against each class of an element.
+    /// It is only used by [`MathJax::typeset_html_with`](crate::MathJax::typeset_html_with), which works on the structure of the HTML rather than its text.
+    pub ignore_html_class: String,
+    /// The class of HTML elements whose contents are searched for math, even within an element which is skipped, defaults to `mathjax_process`.
+    ///
+    /// As with [`DocumentOptions::ignore_html_class`], this is a regular expression only used by [`MathJax::typeset_html_with`](crate::MathJax::typeset_html_with).
+    pub process_html_class: String,
+    /// What each piece of math is replaced with.
+    pub output: DocumentOutput,
+    /// The options each piece of math is rendered with.
+    pub render: RenderOptions,
+}
+
+impl Default for DocumentOptions {
+    fn default() -> Self {
+        let pairs = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|&(start, end)| (start.to_string(), end.to_string()))
+                .collect()
+        };
+        DocumentOptions {
+            inline_math: pairs(&[("$", "$"), (r"\(", r"\)")]),
+            display_math: pairs(&[("$$", "$$"), (r"\[", r"\]")]),
+            process_escapes: true,
+            process_environments: true,
+            skip_code: true,
+            decode_entities: true,
+            ignore_html_class: String::from("mathjax_ignore"),
+            process_html_class: String::from("mathjax_process"),
+            output: DocumentOutput::default(),
+            render: RenderOptions::default(),
+        }
+    }
+}
+
+/// What math in a document is replaced with, see [`DocumentOptions::output`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DocumentOutput {
+    /// The rendered SVG, wrapped in an `<mjx-container>` element as MathJax does in a web page.
+    #[default]
+    Svg,
+    /// The MathML of the expression, as a `<math>` element.
+    MathMl,
+}
+
+/// A piece of math found in a document, see [`DocumentOptions::find_math`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundMath {
+    /// The location of the math (including its delimiters) in the document, as byte offsets.
+    pub span: Range<usize>,
+    /// The TeX expression, without its delimiters (and with HTML character references decoded, see [`DocumentOptions::decode_entities`]).
+    pub tex: String,
+    /// Whether this is display (rather than inline) math.
+    pub display: bool,
+}
+
+/// Something found in a document which is replaced.
+pub(crate) enum Found {
+    Math(FoundMath),
+    /// An escaped character (e.g. `\$`), which is replaced with the character itself.
+    Escape(Range<usize>),
+}
+
+impl DocumentOptions {
+    /// Finds every piece of math in the document, in the order they appear.
+    ///
+    /// Math ends at the first end delimiter which isn't within braces (or escaped with a backslash),
+    /// and a start delimiter with no matching end is left as text.
+    pub fn find_math(&self, document: &str) -> Vec<FoundMath> {
+        self.find(document)
+            .into_iter()
+            .filter_map(|found| match found {
+                Found::Math(math) => Some(math),
+                Found::Escape(_) => None,
+            })
+            .collect()
+    }
+
+    /// Finds every piece of math and escaped character in the document.
+    pub(crate) fn find(&self, document: &str) -> Vec<Found> {
+        // Longer delimiters are tried first, so `$$` is not mistaken for an empty `$...$`
+        let mut delimiters: Vec<(&str, &str, bool)> =
+            (self.inline_math.iter().map(|pair| (pair, false)))
+                .chain(self.display_math.iter().map(|pair| (pair, true)))
+                .filter(|((start, end), _)| !start.is_empty() && !end.is_empty())
+                .map(|((start, end), display)| (start.as_str(), end.as_str(), display))
+                .collect();
+        delimiters.sort_by_key(|&(start, _, _)| std::cmp::Reverse(start.len()));
+
+        let mut found = Vec::new();
+        let mut i = 0;
+        while i < document.len() {
+            let rest = &document[i..];
+
+            if self.skip_code {
+                if let Some(end) = skip_code(document, i) {
+                    i = end;
+                    continue;
+                }
+            }
+
+            if self.process_escapes && rest.starts_with(r"\$") {
+                found.push(Found::Escape(i..i + 2));
+                i += 2;
+                continue;
+            }
+
+            if self.process_environments && rest.starts_with(r"\begin{") {
+                if let Some(end) = find_environment(document, i) {
+                    found.push(Found::Math(FoundMath {
+                        span: i..end,
+                        tex: self.tex(&document[i..end]),
+                        display: true,
+                    }));
+                    i = end;
+                    continue;
+                }
+            }
+
+            if let Some(&(start, end, display)) = delimiters
+                .iter()
+                .find(|(start, _, _)| rest.starts_with(start))
+            {
+                let from = i + start.len();
+                match find_end(document, from, end, self.skip_code) {
+                    Some(to) => {
+                        found.push(Found::Math(FoundMath {
+                            span: i..to + end.len(),
+                            tex: self.tex(&document[from..to]),
+                            display,
+                        }));
+                        i = to + end.len();
+                    }
+                    None => i = from,
+                }
+                continue;
+            }
+
+            // An escaped character never starts math (e.g. `\\(` is a line break followed by a bracket)
+            let mut chars = rest.chars();
+            if chars.next() == Some('\\') {
+                i += 1 + chars.next().map_or(0, char::len_utf8);
+            } else {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+
+        found
+    }
+
+    /// Returns the TeX of a piece of math found in a document.
+    fn tex(&self, math: &str) -> String {
+        if self.decode_entities {
+            decode_entities(math).into_owned()
+        } else {
+            math.to_string()
+        }
+    }
+}
+
+/// Decodes the HTML character references in some text, any which aren't recognised are left as they are.
+///
+/// Only the numeric references and the named references for the characters which must be escaped in HTML are decoded.
+fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        // References are short, so `&` as used in TeX (to align columns) doesn't search far for a `;`
+        let reference = rest.as_bytes()[1..]
+            .iter()
+            .take(32)
+            .position(|&byte| byte == b';')
+            .map(|end| &rest[1..end + 1])
+            .and_then(|name| {
+                let c = match name {
+                    "lt" => '<',
+                    "gt" => '>',
+                    "amp" => '&',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    "nbsp" => '\u{A0}',
+                    _ => {
+                        let number = name.strip_prefix('#')?;
+                        let code = match number.strip_prefix(['x', 'X']) {
+                            Some(hex) if hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                                u32::from_str_radix(hex, 16)
+                            }
+                            None if number.chars().all(|c| c.is_ascii_digit()) => number.parse(),
+                            _ => return None,
+                        };
+                        char::from_u32(code.ok()?)?
+                    }
+                };
+                Some((c, name.len() + 2))
+            });
+        match reference {
+            Some((c, length)) => {
+                decoded.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+/// Returns the start of the end delimiter of math whose TeX starts at `from` (i.e. the end of the TeX), if there is one.
+///
+/// When skipping code, math never continues into an element whose contents are skipped.
+fn find_end(document: &str, from: usize, end: &str, skip_code: bool) -> Option<usize> {
+    let mut braces = 0usize;
+    let mut i = from;
+    while i < document.len() {
+        let rest = &document[i..];
+        if braces == 0 && rest.starts_with(end) {
+            return Some(i);
+        }
+        if skip_code && skip_element(document, i).is_some() {
+            return None;
+        }
+        let mut chars = rest.chars();
+        match chars.next()? {
+            '\\' => i += 1 + chars.next().map_or(0, char::len_utf8),
+            c => {
+                match c {
+                    '{' => braces += 1,
+                    '}' => braces = braces.saturating_sub(1),
+                    _ => {}
+                }
+                i += c.len_utf8();
+            }
+        }
+    }
+    None
+}
+
+/// Returns the end of the environment whose `\begin{...}` is at `start`, if it has a matching `\end{...}`.
+fn find_environment(document: &str, start: usize) -> Option<usize> {
+    let name_start = start + r"\begin{".len();
+    let name = &document[name_start..name_start + document[name_start..].find('}')?];
+    let (begin, end) = (format!(r"\begin{{{name}}}"), format!(r"\end{{{name}}}"));
+
+    // Environments of the same name may be nested
+    let mut depth = 0usize;
+    let mut i = start;
+    while i < document.len() {
+        let rest = &document[i..];
+        if rest.starts_with(&begin) {
+            depth += 1;
+            i += begin.len();
+        } else if rest.starts_with(&end) {
+            depth -= 1;
+            i += end.len();
+            if depth == 0 {
+                return Some(i);
+            }
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    None
+}
+
+/// Returns the end of the code starting at `start`, if there is any.
+fn skip_code(document: &str, start: usize) -> Option<usize> {
+    let rest = &document[start..];
+
+    // A code span ends at the next run of the same number of backticks,
+    //  otherwise the backticks themselves are just text
+    if rest.starts_with('`') {
+        let ticks = rest.len() - rest.trim_start_matches('`').len();
+        let mut i = start + ticks;
+        while let Some(offset) = document[i..].find('`') {
+            let run = &document[i + offset..];
+            let length = run.len() - run.trim_start_matches('`').len();
+            if length == ticks {
+                return Some(i + offset + length);
+            }
+            i += offset + length;
+        }
+        return Some(start + ticks);
+    }
+
+    skip_element(document, start)
+}
+
+/// Returns the end of the element starting at `start`, if it is one whose contents are skipped (see [`SKIP_TAGS`]).
+///
+/// The element ends after its closing tag (or at the end of the document).
+fn skip_element(document: &str, start: usize) -> Option<usize> {
+    let rest = &document[start..];
+    let is_tag = |text: &str, tag: &str| {
+        text.get(..tag.len())
+            .is_some_and(|name| name.eq_ignore_ascii_case(tag))
+    };
+    let tag = SKIP_TAGS.iter().find(|tag| {
+        rest.strip_prefix('<').is_some_and(|rest| {
+            is_tag(rest, tag)
+                && rest[tag.len()..]
+                    .starts_with(|c: char| c == '>' || c == '/' || c.is_ascii_whitespace())
+        })
+    })?;
+    let end = rest
+        .match_indices("</")
+        .map(|(offset, _)| start + offset)
+        .find(|&close| is_tag(&document[close + 2..], tag))
+        .map_or(document.len(), |close| {
+            document[close..]
+                .find('>')
+                .map_or(document.len(), |end| close + end + 1)
+        });
+    Some(end)
+}
+
+/// Replaces everything found in the document, each piece of math is replaced with the next of the given replacements.
+pub(crate) fn substitute(
+    document: &str,
+    found: &[Found],
+    replacements: impl IntoIterator<Item = String>,
+) -> String {
+    let mut replacements = replacements.into_iter();
+    let mut output = String::with_capacity(document.len());
+    let mut last = 0;
+    for found in found {
+        let span = match found {
+            Found::Math(math) => math.span.clone(),
+            Found::Escape(span) => span.clone(),
+        };
+        output.push_str(&document[last..span.start]);
+        match found {
+            Found::Math(_) => output.push_str(&replacements.next().unwrap_or_default()),
+            // The backslash is dropped, leaving the character
+            Found::Escape(span) => output.push_str(&document[span.start + 1..span.end]),
+        }
+        last = span.end;
+    }
+    output.push_str(&document[last..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the TeX and whether it is displayed of each piece of math in the document.
+    fn math(document: &str) -> Vec<(String, bool)> {
+        DocumentOptions::default()
+            .find_math(document)
+            .into_iter()
+            .map(|math| (math.tex, math.display))
+            .collect()
+    }
+
+    /// Replaces each piece of math in the document with its TeX in brackets.
+    fn replaced(document: &str) -> String {
+        let found = DocumentOptions::default().find(document);
+        let replacements: Vec<_> = found
+            .iter()
+            .filter_map(|found| match found {
+                Found::Math(math) => Some(format!("[{}]", math.tex)),
+                Found::Escape(_) => None,
+            })
+            .collect();
+        substitute(document, &found, replacements)
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(replaced(r"costs \$5 or $x$"), "costs $5 or [x]");
+        assert_eq!(replaced(r"\$5 and \$6"), "$5 and $6");
+        // Within math the backslash is left for TeX
+        assert_eq!(math(r"$\$5$"), [(String::from(r"\$5"), false)]);
+        // Only the dollar sign is escaped
+        assert_eq!(replaced(r"\\(x\\)"), r"\\(x\\)");
+
+        let options = DocumentOptions {
+            process_escapes: false,
+            ..Default::default()
+        };
+        assert!(options.find(r"\$5").is_empty());
+        assert_eq!(DocumentOptions::default().find(r"\$5").len(), 1);
+    }
+
+    #[test]
+    fn environments() {
+        let nested = r"\begin{matrix}\begin{matrix}a\end{matrix}\end{matrix}";
+        assert_eq!(
+            math(&format!("before {nested} after")),
+            [(nested.to_string(), true)]
+        );
+        let different = r"\begin{align}\begin{array}{c}a\end{array}\end{align}";
+        assert_eq!(math(different), [(different.to_string(), true)]);
+        // Without a matching end, the environment is left as text (but those within it are still found)
+        assert_eq!(
+            math(r"\begin{matrix}\begin{matrix}a\end{matrix}"),
+            [(String::from(r"\begin{matrix}a\end{matrix}"), true)]
+        );
+        assert_eq!(math(r"\begin{matrix"), []);
+    }
+
+    #[test]
+    fn unterminated() {
+        assert_eq!(math("costs $5"), []);
+        assert_eq!(math(r"\(x"), []);
+        assert_eq!(math("$$x$"), []);
+        // An unterminated start doesn't swallow the math after it
+        assert_eq!(math(r"\(x and $y$"), [(String::from("y"), false)]);
+        // Braces must be balanced for math to end
+        assert_eq!(math("${$ and $}$"), [(String::from("{$ and $}"), false)]);
+        assert_eq!(math("${x"), []);
+    }
+
+    #[test]
+    fn backticks() {
+        assert_eq!(math("`$x$` $y$"), [(String::from("y"), false)]);
+        assert_eq!(math("``$x$ ` $z$`` $y$"), [(String::from("y"), false)]);
+        // A run of backticks without a matching run is just text
+        assert_eq!(
+            math("``$x$` $y$"),
+            [(String::from("x"), false), (String::from("y"), false)]
+        );
+        assert_eq!(math("```\n$x$\n```\n$y$"), [(String::from("y"), false)]);
+
+        let options = DocumentOptions {
+            skip_code: false,
+            ..Default::default()
+        };
+        assert_eq!(options.find_math("`$x$`").len(), 1);
+    }
+
+    #[test]
+    fn skipped_elements() {
+        assert_eq!(
+            math("<pre>$x$</pre>$y$<code>$z$</code>"),
+            [(String::from("y"), false)]
+        );
+        assert_eq!(
+            math("<PRE class=\"a\">\n$x$\n</PRE>$$y$$"),
+            [(String::from("y"), true)]
+        );
+        // Math never continues into (or out of) a skipped element
+        assert_eq!(math("$x <code>$</code> y$"), []);
+        assert_eq!(math("<code>$x</code>$"), []);
+        // Only whole tag names are skipped
+        assert_eq!(math("<precise>$x$</precise>"), [(String::from("x"), false)]);
+        // An unclosed element is skipped to the end of the document
+        assert_eq!(math("$x$ <pre>$y$"), [(String::from("x"), false)]);
+    }
+
+    #[test]
+    fn entities() {
+        assert_eq!(
+            math("$a &lt; b &amp;&amp; c &#x3E; d &#62; e$"),
+            [(String::from("a < b && c > d > e"), false)]
+        );
+        // Alignments and unknown references are left as they are
+        assert_eq!(
+            decode_entities(r"a & b &foo; &#xZZ; &#+1; &"),
+            r"a & b &foo; &#xZZ; &#+1; &"
+        );
+        assert_eq!(decode_entities("&nbsp;&quot;&apos;"), "\u{A0}\"'");
+
+        let options = DocumentOptions {
+            decode_entities: false,
+            ..Default::default()
+        };
+        assert_eq!(options.find_math("$a &lt; b$")[0].tex, "a &lt; b");
+    }
+}
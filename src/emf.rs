@@ -0,0 +1,263 @@
+use crate::Render;
+use resvg::usvg::PathSegment;
+
+/// The number of logical units (the coordinate system of the records) per point.
+const UNITS_PER_POINT: f64 = 100.0;
+
+// The types of the records used, from the EMF specification ([MS-EMF] section 2.1.1)
+const EMR_HEADER: u32 = 1;
+const EMR_POLYBEZIERTO: u32 = 5;
+const EMR_EOF: u32 = 14;
+const EMR_SETPOLYFILLMODE: u32 = 19;
+const EMR_MOVETOEX: u32 = 27;
+const EMR_SELECTOBJECT: u32 = 37;
+const EMR_CREATEPEN: u32 = 38;
+const EMR_CREATEBRUSHINDIRECT: u32 = 39;
+const EMR_DELETEOBJECT: u32 = 40;
+const EMR_LINETO: u32 = 54;
+const EMR_BEGINPATH: u32 = 59;
+const EMR_ENDPATH: u32 = 60;
+const EMR_CLOSEFIGURE: u32 = 61;
+const EMR_FILLPATH: u32 = 62;
+const EMR_STROKEANDFILLPATH: u32 = 63;
+const EMR_STROKEPATH: u32 = 64;
+
+/// The handles of the objects created for each shape.
+const BRUSH: u32 = 1;
+const PEN: u32 = 2;
+
+/// Writes the records of an EMF file, keeping track of what is needed for the header.
+struct Records {
+    data: Vec<u8>,
+    count: u32,
+}
+
+impl Records {
+    fn push(&mut self, kind: u32, fields: &[u32]) {
+        self.data.extend_from_slice(&kind.to_le_bytes());
+        self.data
+            .extend_from_slice(&(8 + 4 * fields.len() as u32).to_le_bytes());
+        for field in fields {
+            self.data.extend_from_slice(&field.to_le_bytes());
+        }
+        self.count += 1;
+    }
+}
+
+impl Render {
+    /// Converts the render into an Enhanced Metafile (EMF) image, this is the vector format used by Microsoft Office.
+    ///
+    /// The formula is drawn with vector paths and sized to match text of the given font size (in points).
+    /// Any text (e.g. from `\text{...}`) is drawn with the system fonts, see [`Render::to_emf_with`] to use other fonts.
+    /// The format has no support for transparency, so any partial opacity (e.g. from [`Style::opacity`](crate::Style::opacity)) is ignored and only fully transparent paths are left out.
+    ///
+    /// Coordinates are in hundredths of a point, and the paths are written with the `EMR_MOVETOEX`, `EMR_LINETO`, `EMR_POLYBEZIERTO` and `EMR_CLOSEFIGURE` records,
+    /// so the image can be read back and compared against [`Render::rasterize`]:
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{MathJax, RasterOptions, RasterSize};
+    /// use resvg::tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Transform};
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let render = renderer.render(r#"y=\frac{1}{x}"#).unwrap();
+    /// let emf = render.to_emf(48.0).unwrap();
+    ///
+    /// // Every EMF file starts with a header record, and contains its own size
+    /// assert_eq!(emf[..4], 1u32.to_le_bytes());
+    /// assert_eq!(emf[48..52], (emf.len() as u32).to_le_bytes());
+    ///
+    /// // At 72 DPI, a point is a pixel
+    /// let options = RasterOptions {
+    ///     size: RasterSize::FontSize { points: 48.0, dpi: 72.0 },
+    ///     ..Default::default()
+    /// };
+    /// let expected = render.to_pixmap(&options).unwrap();
+    ///
+    /// // Draw the paths from the records
+    /// let field = |offset: usize| i32::from_le_bytes(emf[offset..offset + 4].try_into().unwrap());
+    /// let point = |offset: usize| (field(offset) as f32 / 100.0, field(offset + 4) as f32 / 100.0);
+    /// let mut actual = Pixmap::new(expected.width(), expected.height()).unwrap();
+    /// let (mut path, mut rule) = (PathBuilder::new(), FillRule::Winding);
+    /// let mut offset = field(4) as usize;
+    /// while offset < emf.len() {
+    ///     let (kind, size) = (field(offset), field(offset + 4) as usize);
+    ///     match kind {
+    ///         // EMR_SETPOLYFILLMODE
+    ///         19 => rule = if field(offset + 8) == 1 { FillRule::EvenOdd } else { FillRule::Winding },
+    ///         // EMR_MOVETOEX and EMR_LINETO
+    ///         27 => path.move_to(point(offset + 8).0, point(offset + 8).1),
+    ///         54 => path.line_to(point(offset + 8).0, point(offset + 8).1),
+    ///         // EMR_POLYBEZIERTO, which has bounds and a count before its points
+    ///         5 => {
+    ///             for curve in 0..field(offset + 24) as usize / 3 {
+    ///                 let points = offset + 28 + curve * 24;
+    ///                 let [(x1, y1), (x2, y2), (x, y)] = [points, points + 8, points + 16].map(point);
+    ///                 path.cubic_to(x1, y1, x2, y2, x, y);
+    ///             }
+    ///         }
+    ///         // EMR_CLOSEFIGURE
+    ///         61 => path.close(),
+    ///         // EMR_FILLPATH
+    ///         62 => {
+    ///             let finished = std::mem::replace(&mut path, PathBuilder::new()).finish().unwrap();
+    ///             actual.fill_path(&finished, &Paint::default(), rule, Transform::identity(), None);
+    ///         }
+    ///         _ => {}
+    ///     }
+    ///     offset += size;
+    /// }
+    ///
+    /// // Only the anti-aliased edges should differ
+    /// let different = (expected.pixels().iter().zip(actual.pixels()))
+    ///     .filter(|(expected, actual)| expected.alpha().abs_diff(actual.alpha()) > 64)
+    ///     .count();
+    /// assert!(different < expected.pixels().len() / 100);
+    /// # }
+    /// ```
+    pub fn to_emf(&self, font_size: f64) -> Result<Vec<u8>, crate::RasterError> {
+        self.to_emf_with(font_size, &crate::FontDatabase::System)
+    }
+
+    /// Converts the render into an EMF image (as [`Render::to_emf`]), any text is drawn with the given fonts.
+    pub fn to_emf_with(
+        &self,
+        font_size: f64,
+        fonts: &crate::FontDatabase,
+    ) -> Result<Vec<u8>, crate::RasterError> {
+        let drawing = self.to_drawing(font_size, fonts)?;
+        let unit = |value: f64| (value * UNITS_PER_POINT).round() as i32 as u32;
+        let point = |x: f64, y: f64| [unit(x), unit(y)];
+        let (width, height) = (unit(drawing.width), unit(drawing.height));
+        // Bounds are inclusive
+        let bounds = [0, 0, width.saturating_sub(1), height.saturating_sub(1)];
+        let colorref = |[red, green, blue]: [u8; 3]| u32::from_le_bytes([red, green, blue, 0]);
+
+        let mut records = Records {
+            data: Vec::new(),
+            count: 0,
+        };
+        for shape in &drawing.shapes {
+            let fill = shape.fill.as_ref().filter(|fill| fill.opacity > 0.0);
+            let stroke = shape.stroke.as_ref().filter(|stroke| stroke.opacity > 0.0);
+            if fill.is_none() && stroke.is_none() {
+                continue;
+            }
+            if let Some(fill) = fill {
+                // ALTERNATE (even-odd) or WINDING (non-zero)
+                records.push(EMR_SETPOLYFILLMODE, &[if fill.even_odd { 1 } else { 2 }]);
+                // A solid (BS_SOLID) brush
+                records.push(
+                    EMR_CREATEBRUSHINDIRECT,
+                    &[BRUSH, 0, colorref(fill.color), 0],
+                );
+                records.push(EMR_SELECTOBJECT, &[BRUSH]);
+            }
+            if let Some(stroke) = stroke {
+                // A solid (PS_SOLID) pen
+                let width = unit(stroke.width).max(1);
+                records.push(EMR_CREATEPEN, &[PEN, 0, width, 0, colorref(stroke.color)]);
+                records.push(EMR_SELECTOBJECT, &[PEN]);
+            }
+
+            records.push(EMR_BEGINPATH, &[]);
+            let mut curves = Vec::new();
+            let flush = |records: &mut Records, curves: &mut Vec<u32>| {
+                if !curves.is_empty() {
+                    let mut fields = bounds.to_vec();
+                    fields.push(curves.len() as u32 / 2);
+                    fields.append(curves);
+                    records.push(EMR_POLYBEZIERTO, &fields);
+                }
+            };
+            for segment in &shape.segments {
+                match *segment {
+                    PathSegment::CurveTo {
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        x,
+                        y,
+                    } => {
+                        curves.extend(point(x1, y1));
+                        curves.extend(point(x2, y2));
+                        curves.extend(point(x, y));
+                    }
+                    segment => {
+                        flush(&mut records, &mut curves);
+                        match segment {
+                            PathSegment::MoveTo { x, y } => {
+                                records.push(EMR_MOVETOEX, &point(x, y))
+                            }
+                            PathSegment::LineTo { x, y } => records.push(EMR_LINETO, &point(x, y)),
+                            _ => records.push(EMR_CLOSEFIGURE, &[]),
+                        }
+                    }
+                }
+            }
+            flush(&mut records, &mut curves);
+            records.push(EMR_ENDPATH, &[]);
+
+            let operation = match (fill, stroke) {
+                (Some(_), Some(_)) => EMR_STROKEANDFILLPATH,
+                (Some(_), None) => EMR_FILLPATH,
+                (None, _) => EMR_STROKEPATH,
+            };
+            records.push(operation, &bounds);
+
+            // Select the stock NULL_BRUSH and NULL_PEN objects, so the created objects can be deleted
+            if fill.is_some() {
+                records.push(EMR_SELECTOBJECT, &[0x8000_0005]);
+                records.push(EMR_DELETEOBJECT, &[BRUSH]);
+            }
+            if stroke.is_some() {
+                records.push(EMR_SELECTOBJECT, &[0x8000_0008]);
+                records.push(EMR_DELETEOBJECT, &[PEN]);
+            }
+        }
+        // No palette entries, the offset to them, and the size of this record
+        records.push(EMR_EOF, &[0, 16, 20]);
+
+        // The reference device has a logical unit per pixel, on an A4 page
+        let millimeters = [210, 297];
+        let device = millimeters.map(|millimeters: u32| unit(millimeters as f64 / 25.4 * 72.0));
+        // The frame is in hundredths of a millimeter, and is also inclusive
+        let hundredths = |value: f64| (value / 72.0 * 2540.0).round() as u32;
+        let frame = [
+            0,
+            0,
+            hundredths(drawing.width).saturating_sub(1),
+            hundredths(drawing.height).saturating_sub(1),
+        ];
+
+        let mut header = Records {
+            data: Vec::new(),
+            count: 0,
+        };
+        let size = 88 + records.data.len() as u32;
+        let mut fields = Vec::new();
+        fields.extend(bounds);
+        fields.extend(frame);
+        fields.extend([
+            // The " EMF" signature and format version
+            0x464D_4520,
+            0x0001_0000,
+            size,
+            records.count + 1,
+            // The number of handles (including the reserved handle 0), and a reserved field
+            u32::from_le_bytes([3, 0, 0, 0]),
+            // No description
+            0,
+            0,
+            // No palette entries
+            0,
+        ]);
+        fields.extend(device);
+        fields.extend(millimeters);
+        header.push(EMR_HEADER, &fields);
+
+        let mut emf = header.data;
+        emf.append(&mut records.data);
+        Ok(emf)
+    }
+}
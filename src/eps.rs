@@ -0,0 +1,178 @@
+use crate::metrics::format_number;
+use crate::Render;
+use resvg::usvg::PathSegment;
+
+impl Render {
+    /// Converts the render into an Encapsulated PostScript (EPS) figure, cropped tightly to the render.
+    ///
+    /// The formula is drawn with vector paths and sized to match text of the given font size (in points).
+    /// Any text (e.g. from `\text{...}`) is drawn with the system fonts, see [`Render::to_eps_with`] to use other fonts.
+    /// PostScript has no support for transparency, so any partial opacity (e.g. from [`Style::opacity`](crate::Style::opacity)) is ignored and only fully transparent paths are left out.
+    ///
+    /// The paths are written with the operators `m` (moveto), `l` (lineto), `c` (curveto), `h` (closepath), `f` (fill), `f*` (eofill), `rg` (setrgbcolor) and `S` (stroke),
+    /// so the figure can be read back and compared against [`Render::rasterize`]:
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{MathJax, RasterOptions, RasterSize};
+    /// use resvg::tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Transform};
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let render = renderer.render(r#"y=\frac{1}{x}"#).unwrap();
+    /// let eps = render.to_eps(48.0).unwrap();
+    ///
+    /// // At 72 DPI, a point is a pixel
+    /// let options = RasterOptions {
+    ///     size: RasterSize::FontSize { points: 48.0, dpi: 72.0 },
+    ///     ..Default::default()
+    /// };
+    /// let expected = render.to_pixmap(&options).unwrap();
+    ///
+    /// // Draw the paths from the figure (which has its origin at the bottom left)
+    /// let bounds = eps.lines().find_map(|line| line.strip_prefix("%%HiResBoundingBox: ")).unwrap();
+    /// let height: f32 = bounds.split_whitespace().nth(3).unwrap().parse().unwrap();
+    /// let mut actual = Pixmap::new(expected.width(), expected.height()).unwrap();
+    /// let flip = Transform::from_row(1.0, 0.0, 0.0, -1.0, 0.0, height);
+    /// let (mut stack, mut path) = (Vec::<f32>::new(), PathBuilder::new());
+    /// let body = eps.split("%%EndProlog").nth(1).unwrap();
+    /// for token in body.split_whitespace() {
+    ///     match token {
+    ///         "m" => path.move_to(stack[0], stack[1]),
+    ///         "l" => path.line_to(stack[0], stack[1]),
+    ///         "c" => path.cubic_to(stack[0], stack[1], stack[2], stack[3], stack[4], stack[5]),
+    ///         "h" => path.close(),
+    ///         "f" | "f*" => {
+    ///             let rule = if token == "f" { FillRule::Winding } else { FillRule::EvenOdd };
+    ///             let finished = std::mem::replace(&mut path, PathBuilder::new()).finish().unwrap();
+    ///             actual.fill_path(&finished, &Paint::default(), rule, flip, None);
+    ///         }
+    ///         token => match token.parse() {
+    ///             Ok(number) => {
+    ///                 stack.push(number);
+    ///                 continue;
+    ///             }
+    ///             Err(_) => {}
+    ///         },
+    ///     }
+    ///     stack.clear();
+    /// }
+    ///
+    /// // Only the anti-aliased edges should differ
+    /// let different = (expected.pixels().iter().zip(actual.pixels()))
+    ///     .filter(|(expected, actual)| expected.alpha().abs_diff(actual.alpha()) > 64)
+    ///     .count();
+    /// assert!(different < expected.pixels().len() / 100);
+    /// # }
+    /// ```
+    pub fn to_eps(&self, font_size: f64) -> Result<String, crate::RasterError> {
+        self.to_eps_with(font_size, &crate::FontDatabase::System)
+    }
+
+    /// Converts the render into an EPS figure (as [`Render::to_eps`]), any text is drawn with the given fonts.
+    pub fn to_eps_with(
+        &self,
+        font_size: f64,
+        fonts: &crate::FontDatabase,
+    ) -> Result<String, crate::RasterError> {
+        let drawing = self.to_drawing(font_size, fonts)?;
+
+        let mut eps = String::new();
+        eps.push_str("%!PS-Adobe-3.0 EPSF-3.0\n");
+        eps.push_str(&format!(
+            "%%BoundingBox: 0 0 {} {}\n",
+            drawing.width.ceil(),
+            drawing.height.ceil()
+        ));
+        eps.push_str(&format!(
+            "%%HiResBoundingBox: 0 0 {} {}\n",
+            format_number(drawing.width),
+            format_number(drawing.height)
+        ));
+        eps.push_str("%%Creator: mathjax\n");
+        eps.push_str("%%LanguageLevel: 2\n");
+        eps.push_str("%%EndComments\n");
+        eps.push_str("%%BeginProlog\n");
+        for (name, operator) in [
+            ("m", "moveto"),
+            ("l", "lineto"),
+            ("c", "curveto"),
+            ("h", "closepath"),
+            ("f", "fill"),
+            ("f*", "eofill"),
+            ("rg", "setrgbcolor"),
+            ("S", "stroke"),
+        ] {
+            eps.push_str(&format!("/{name} {{{operator}}} bind def\n"));
+        }
+        eps.push_str("%%EndProlog\n");
+
+        // PostScript coordinates start at the bottom left, with the y axis pointing up
+        let y = |y: f64| format_number(drawing.height - y);
+        eps.push_str("gsave\n");
+        for shape in &drawing.shapes {
+            let fill = shape.fill.as_ref().filter(|fill| fill.opacity > 0.0);
+            let stroke = shape.stroke.as_ref().filter(|stroke| stroke.opacity > 0.0);
+            if fill.is_none() && stroke.is_none() {
+                continue;
+            }
+            eps.push_str("newpath\n");
+            for segment in &shape.segments {
+                match *segment {
+                    PathSegment::MoveTo { x, y: y0 } => {
+                        eps.push_str(&format!("{} {} m\n", format_number(x), y(y0)));
+                    }
+                    PathSegment::LineTo { x, y: y0 } => {
+                        eps.push_str(&format!("{} {} l\n", format_number(x), y(y0)));
+                    }
+                    PathSegment::CurveTo {
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        x,
+                        y: y0,
+                    } => {
+                        eps.push_str(&format!(
+                            "{} {} {} {} {} {} c\n",
+                            format_number(x1),
+                            y(y1),
+                            format_number(x2),
+                            y(y2),
+                            format_number(x),
+                            y(y0)
+                        ));
+                    }
+                    PathSegment::ClosePath => eps.push_str("h\n"),
+                }
+            }
+
+            let rgb = |color: [u8; 3]| {
+                color
+                    .map(|channel| format_number(channel as f64 / 255.0))
+                    .join(" ")
+            };
+            if let Some(fill) = fill {
+                let operator = if fill.even_odd { "f*" } else { "f" };
+                match stroke {
+                    // Filling consumes the path, so it must be saved to be stroked afterwards
+                    Some(_) => eps.push_str(&format!(
+                        "gsave {} rg {operator} grestore\n",
+                        rgb(fill.color)
+                    )),
+                    None => eps.push_str(&format!("{} rg {operator}\n", rgb(fill.color))),
+                }
+            }
+            if let Some(stroke) = stroke {
+                eps.push_str(&format!(
+                    "{} rg {} setlinewidth S\n",
+                    rgb(stroke.color),
+                    format_number(stroke.width)
+                ));
+            }
+        }
+        eps.push_str("grestore\n");
+        eps.push_str("showpage\n");
+        eps.push_str("%%EOF\n");
+
+        Ok(eps)
+    }
+}
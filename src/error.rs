@@ -34,4 +34,73 @@ pub enum RenderError {
     #[cfg(feature = "browser")]
     #[error("{0}")]
     Browser(#[from] anyhow::Error),
+    /// The requested font is not available in the installed version of MathJax, see [`MathJax::fonts`](crate::MathJax::fonts).
+    #[error("font not available: {0}")]
+    FontUnavailable(crate::Font),
+    /// Spoken text was requested (see [`RenderOptions::speech`](crate::RenderOptions::speech)), but the backend doesn't include the Speech Rule Engine.
+    #[error("speech is not available with this renderer")]
+    SpeechUnavailable,
+    /// Unable to communicate with the renderer, it produced a response that could not be understood.
+    #[error("invalid response from MathJax renderer: {0}")]
+    ProtocolError(#[from] serde_json::Error),
+    /// The MathML produced by the renderer could not be parsed, see [`MathJax::to_mml`](crate::MathJax::to_mml).
+    #[error("{0}")]
+    MmlError(#[from] MmlError),
+}
+
+/// An error parsing a [`Color`](crate::Color), the string was not a valid CSS color.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid color: {0}")]
+pub struct ColorError(pub(crate) String);
+
+/// An error parsing an [`MmlElement`](crate::MmlElement), the string was not valid XML.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid MathML: {0}")]
+pub struct MmlError(pub(crate) String);
+
+/// An error converting a render into an image, see [`Render::rasterize`](crate::Render::rasterize).
+///
+/// This is also the error of the vector output (such as [`Render::into_svg`](crate::Render::into_svg)), which can only fail to parse the SVG.
+///
+/// ```rust
+/// # fn main() {
+/// use mathjax::{MathJax, RasterError};
+///
+/// let renderer = MathJax::new().unwrap();
+/// let render = renderer.render(r#"y=\frac{1}{x}"#).unwrap();
+///
+/// // The image would be far too large to allocate
+/// let error = render.into_image(1e9).unwrap_err();
+/// assert!(matches!(error, RasterError::InvalidSize { .. }));
+/// # }
+/// ```
+#[cfg(feature = "image")]
+#[derive(Debug, thiserror::Error)]
+pub enum RasterError {
+    /// The SVG could not be parsed.
+    #[error("invalid SVG: {0}")]
+    Svg(#[from] resvg::usvg::Error),
+    /// The size of the image is zero or too large to allocate, e.g. from a huge scaling factor.
+    #[error("invalid image size: {width}x{height}")]
+    InvalidSize {
+        /// The requested width (in pixels).
+        width: f64,
+        /// The requested height (in pixels).
+        height: f64,
+    },
+    /// The buffer is too small to hold an image of the given size, or its stride is shorter than a row of pixels.
+    #[error("invalid buffer: {length} bytes long but {required} bytes are needed")]
+    InvalidBuffer {
+        /// The length of the buffer (in bytes).
+        length: usize,
+        /// The minimum length needed (in bytes).
+        required: usize,
+    },
+    /// The layout metrics of the render are missing, so it can't be sized to match a font size.
+    /// This only happens if the SVG was not produced by MathJax, see [`Render::metrics`](crate::Render::metrics).
+    #[error("missing layout metrics")]
+    MissingMetrics,
+    /// The image could not be encoded.
+    #[error("unable to encode image: {0}")]
+    Encoding(#[from] image::ImageError),
 }
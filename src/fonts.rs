@@ -0,0 +1,92 @@
+use resvg::usvg::fontdb;
+use std::sync::{Arc, OnceLock};
+
+/// The fonts used to draw any text within a render which MathJax doesn't draw with its own glyphs,
+/// this is only needed for characters outside of the MathJax font (e.g. `\text{日本語}`).
+///
+/// For output which never depends on the machine it is produced on (e.g. for golden image tests),
+/// use either [`FontDatabase::Empty`] or [`FontDatabase::from_data`] with fonts embedded in the program:
+/// ```rust
+/// # fn main() {
+/// use mathjax::{FontDatabase, MathJax, RasterOptions};
+///
+/// let renderer = MathJax::new().unwrap();
+/// let render = renderer.render(r#"x = \text{日本語}"#).unwrap();
+/// let options = RasterOptions {
+///     fonts: FontDatabase::Empty,
+///     ..Default::default()
+/// };
+///
+/// // The text is left out, since there are no fonts to draw it with
+/// let image = render.rasterize(&options).unwrap();
+/// assert_eq!(image, render.rasterize(&options).unwrap());
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub enum FontDatabase {
+    /// The fonts installed on the system.
+    ///
+    /// These are loaded the first time they are needed, and then shared by every render.
+    #[default]
+    System,
+    /// No fonts, any text is left out.
+    Empty,
+    /// The fonts within the given database, this can be shared between any number of options.
+    Custom(Arc<fontdb::Database>),
+}
+
+impl FontDatabase {
+    /// Creates a database from the data of the given font files (TrueType, OpenType or collections of either).
+    ///
+    /// The first font is used for every generic font family (e.g. `serif`), which is what MathJax requests text in.
+    pub fn from_data<I>(fonts: I) -> Self
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+    {
+        let mut database = fontdb::Database::new();
+        for font in fonts {
+            database.load_font_data(font);
+        }
+
+        let family = database
+            .faces()
+            .find_map(|face| face.families.first())
+            .map(|(family, _)| family.clone());
+        if let Some(family) = family {
+            database.set_serif_family(family.clone());
+            database.set_sans_serif_family(family.clone());
+            database.set_cursive_family(family.clone());
+            database.set_fantasy_family(family.clone());
+            database.set_monospace_family(family);
+        }
+
+        FontDatabase::Custom(Arc::new(database))
+    }
+
+    /// Returns the underlying database.
+    pub fn database(&self) -> &fontdb::Database {
+        static SYSTEM: OnceLock<fontdb::Database> = OnceLock::new();
+        static EMPTY: OnceLock<fontdb::Database> = OnceLock::new();
+
+        match self {
+            FontDatabase::System => SYSTEM.get_or_init(|| {
+                let mut database = fontdb::Database::new();
+                database.load_system_fonts();
+                database
+            }),
+            FontDatabase::Empty => EMPTY.get_or_init(fontdb::Database::new),
+            FontDatabase::Custom(database) => database,
+        }
+    }
+}
+
+impl PartialEq for FontDatabase {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (FontDatabase::System, FontDatabase::System) => true,
+            (FontDatabase::Empty, FontDatabase::Empty) => true,
+            (FontDatabase::Custom(a), FontDatabase::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
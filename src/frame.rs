@@ -0,0 +1,382 @@
+use crate::metrics::{format_ex, format_number};
+use crate::{Color, Metrics, X_HEIGHT};
+use std::borrow::Cow;
+
+/// Space and a background drawn around a render, see [`Render::set_frame`](crate::Render::set_frame).
+///
+/// All lengths are in `em` (i.e. multiples of the font size), so the frame scales along with the render.
+/// The baseline of the render is kept, so a framed render can still be aligned with the surrounding text using its [`Metrics`].
+/// Equations which stretch to fill their container (e.g. those with a tag) still do, so horizontal space only increases the width they can shrink to.
+///
+/// For example, to give a formula an opaque white background and some breathing room:
+/// ```rust
+/// # fn main() {
+/// use mathjax::{Color, Frame, MathJax, Padding};
+///
+/// let renderer = MathJax::new().unwrap();
+/// let mut render = renderer.render(r#"y=\frac{1}{x}"#).unwrap();
+/// let before = render.metrics().unwrap().em();
+///
+/// render.set_frame(Frame {
+///     background: Some(Color::WHITE),
+///     padding: Padding::uniform(0.5),
+///     ..Default::default()
+/// });
+///
+/// let after = render.metrics().unwrap().em();
+/// assert!((after.width - (before.width + 1.0)).abs() < 0.01);
+/// assert!((after.depth - (before.depth + 0.5)).abs() < 0.01);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Frame {
+    /// The color the area behind the render is filled with.
+    ///
+    /// Defaults to `None`, which leaves the background transparent.
+    pub background: Option<Color>,
+    /// The space added around the render, negative (or NaN) space is treated as none.
+    ///
+    /// Defaults to no padding.
+    pub padding: Padding,
+    /// The minimum width (in `em`) of the framed render, extra space is split evenly between the left and right sides.
+    ///
+    /// Negative (or NaN) widths are treated as `0.0`.
+    ///
+    /// Defaults to `0.0`.
+    pub min_width: f64,
+    /// The minimum height (in `em`) of the framed render, extra space is split evenly between the top and bottom.
+    ///
+    /// Negative (or NaN) heights are treated as `0.0`.
+    ///
+    /// Defaults to `0.0`.
+    pub min_height: f64,
+}
+
+/// The space (in `em`) added to each side of a render, see [`Frame::padding`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Padding {
+    /// The space above the render.
+    pub top: f64,
+    /// The space to the right of the render.
+    pub right: f64,
+    /// The space below the render.
+    pub bottom: f64,
+    /// The space to the left of the render.
+    pub left: f64,
+}
+
+impl Padding {
+    /// Creates padding with the same space on every side.
+    pub fn uniform(padding: f64) -> Self {
+        Padding::symmetric(padding, padding)
+    }
+
+    /// Creates padding with `vertical` space above and below, and `horizontal` space to the left and right.
+    pub fn symmetric(vertical: f64, horizontal: f64) -> Self {
+        Padding {
+            top: vertical,
+            right: horizontal,
+            bottom: vertical,
+            left: horizontal,
+        }
+    }
+}
+
+impl Frame {
+    /// Returns whether the frame leaves a render unchanged.
+    pub fn is_empty(&self) -> bool {
+        *self == Frame::default()
+    }
+
+    /// Applies the frame to the given MathJax SVG.
+    ///
+    /// Equations which stretch to fill their container (e.g. those with a tag) don't have a fixed width to add space beside,
+    /// so their horizontal padding (and minimum width) only increases the width they can shrink to.
+    pub(crate) fn apply<'a>(&self, source: &'a str) -> Cow<'a, str> {
+        if self.is_empty() {
+            return Cow::Borrowed(source);
+        }
+        let (Some(metrics), Some(end)) = (Metrics::parse(source), source.find('>')) else {
+            return Cow::Borrowed(source);
+        };
+
+        let [mut top, mut right, mut bottom, mut left, min_width, min_height] = [
+            self.padding.top,
+            self.padding.right,
+            self.padding.bottom,
+            self.padding.left,
+            self.min_width,
+            self.min_height,
+        ]
+        .map(length);
+        let em = metrics.em();
+        let width = em.width + left + right;
+        if width < min_width {
+            left += (min_width - width) / 2.0;
+            right += (min_width - width) / 2.0;
+        }
+        let height = em.total_height() + top + bottom;
+        if height < min_height {
+            top += (min_height - height) / 2.0;
+            bottom += (min_height - height) / 2.0;
+        }
+
+        let Some(view_box) = metrics.view_box() else {
+            return Cow::Owned(self.apply_full_width(source, metrics, [top, right, bottom, left]));
+        };
+
+        // The viewBox is in thousandths of an em, while the size of the SVG is in ex
+        let ex = metrics.ex();
+        let (x, y) = (view_box.x - left * 1000.0, view_box.y - top * 1000.0);
+        let (width, height) = (
+            view_box.width + (left + right) * 1000.0,
+            view_box.height + (top + bottom) * 1000.0,
+        );
+
+        let mut tag = source[..end].to_string();
+        set_attribute(
+            &mut tag,
+            "width",
+            &format_ex(ex.width + (left + right) / X_HEIGHT),
+        );
+        set_attribute(
+            &mut tag,
+            "height",
+            &format_ex(ex.total_height() + (top + bottom) / X_HEIGHT),
+        );
+        set_attribute(
+            &mut tag,
+            "viewBox",
+            &[x, y, width, height].map(format_number).join(" "),
+        );
+        set_style(
+            &mut tag,
+            "vertical-align",
+            &format_ex(ex.vertical_align() - bottom / X_HEIGHT),
+        );
+
+        let mut framed = String::with_capacity(source.len() + 128);
+        framed.push_str(&tag);
+        framed.push('>');
+        if let Some(background) = self.background {
+            framed.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="{}"></rect>"#,
+                format_number(x),
+                format_number(y),
+                format_number(width),
+                format_number(height),
+                background.hex(),
+                format_number(background.opacity()),
+            ));
+        }
+        framed.push_str(&source[end + 1..]);
+        Cow::Owned(framed)
+    }
+
+    /// Applies the given space around a MathJax SVG which stretches to fill its container.
+    fn apply_full_width(&self, source: &str, metrics: Metrics, space: [f64; 4]) -> String {
+        let [top, right, bottom, left] = space;
+        let (Some(end), Some(close)) = (source.find('>'), source.rfind("</svg>")) else {
+            return source.to_string();
+        };
+
+        // The width is already `100%`, so only the minimum width grows
+        let ex = metrics.ex();
+        let mut tag = source[..end].to_string();
+        set_style(
+            &mut tag,
+            "min-width",
+            &format_ex(ex.width + (left + right) / X_HEIGHT),
+        );
+        set_attribute(
+            &mut tag,
+            "height",
+            &format_ex(ex.total_height() + (top + bottom) / X_HEIGHT),
+        );
+        set_style(
+            &mut tag,
+            "vertical-align",
+            &format_ex(ex.vertical_align() - bottom / X_HEIGHT),
+        );
+
+        let mut framed = String::with_capacity(source.len() + 192);
+        framed.push_str(&tag);
+        framed.push('>');
+        if let Some(background) = self.background {
+            framed.push_str(&format!(
+                r#"<rect x="0" y="0" width="100%" height="100%" fill="{}" fill-opacity="{}"></rect>"#,
+                background.hex(),
+                format_number(background.opacity()),
+            ));
+        }
+        // The content is laid out to the height of the SVG, so it is kept within a viewport of its original height
+        framed.push_str(&format!(
+            r#"<svg y="{}" width="100%" height="{}">"#,
+            format_ex(top / X_HEIGHT),
+            format_ex(ex.total_height()),
+        ));
+        framed.push_str(&source[end + 1..close]);
+        framed.push_str("</svg>");
+        framed.push_str(&source[close..]);
+        framed
+    }
+}
+
+/// Returns a length of space, treating negative (or NaN and infinite) lengths as none.
+fn length(value: f64) -> f64 {
+    if value.is_finite() {
+        value.max(0.0)
+    } else {
+        0.0
+    }
+}
+
+/// Sets the value of an attribute within an element's start tag (without the closing `>`), adding it if it is missing.
+pub(crate) fn set_attribute(tag: &mut String, name: &str, value: &str) {
+    let prefix = format!(" {name}=\"");
+    match tag.find(&prefix) {
+        Some(start) => {
+            let start = start + prefix.len();
+            let end = tag[start..].find('"').map_or(tag.len(), |end| start + end);
+            tag.replace_range(start..end, value);
+        }
+        None => tag.push_str(&format!("{prefix}{value}\"")),
+    }
+}
+
+/// Sets the value of a property within the `style` attribute of an element's start tag (without the closing `>`).
+pub(crate) fn set_style(tag: &mut String, property: &str, value: &str) {
+    let prefix = " style=\"";
+    let style = tag.find(prefix).map_or("", |start| {
+        let start = start + prefix.len();
+        tag[start..].split('"').next().unwrap_or_default()
+    });
+
+    let mut declarations: Vec<String> = style
+        .split(';')
+        .map(str::trim)
+        .filter(|declaration| {
+            !declaration.is_empty()
+                && declaration
+                    .split_once(':')
+                    .is_none_or(|(name, _)| name.trim() != property)
+        })
+        .map(ToString::to_string)
+        .collect();
+    declarations.push(format!("{property}: {value}"));
+
+    set_attribute(tag, "style", &format!("{};", declarations.join("; ")));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="8.258ex" height="4.839ex" role="img" style="vertical-align: -1.577ex;" viewBox="0 -1442 3650 2139"><g></g></svg>"#;
+
+    #[test]
+    fn empty() {
+        assert!(matches!(
+            Frame::default().apply(SOURCE),
+            Cow::Borrowed(SOURCE)
+        ));
+    }
+
+    #[test]
+    fn padding() {
+        let frame = Frame {
+            background: Some(Color::WHITE),
+            padding: Padding::symmetric(0.5, 0.25),
+            ..Default::default()
+        };
+        let framed = frame.apply(SOURCE);
+        assert!(framed.contains(r#" viewBox="-250 -1942 4150 3139""#));
+        assert!(framed.contains(
+            r##"><rect x="-250" y="-1942" width="4150" height="3139" fill="#ffffff" fill-opacity="1"></rect><g></g></svg>"##
+        ));
+
+        let (before, after) = (
+            Metrics::parse(SOURCE).unwrap().em(),
+            Metrics::parse(&framed).unwrap().em(),
+        );
+        assert!((after.width - (before.width + 0.5)).abs() < 0.001);
+        assert!((after.height - (before.height + 0.5)).abs() < 0.001);
+        assert!((after.depth - (before.depth + 0.5)).abs() < 0.001);
+    }
+
+    #[test]
+    fn minimum_size() {
+        let frame = Frame {
+            min_width: 5.0,
+            min_height: 0.5,
+            ..Default::default()
+        };
+        let framed = frame.apply(SOURCE);
+        // The extra width is split between the sides, the height is already large enough
+        let before = Metrics::parse(SOURCE).unwrap().em();
+        let after = Metrics::parse(&framed).unwrap();
+        assert!((after.em().width - 5.0).abs() < 0.001);
+        assert_eq!(after.em().total_height(), before.total_height());
+        let view_box = after.view_box().unwrap();
+        assert!((view_box.x + (5000.0 - 3650.0) / 2.0).abs() < 0.1);
+        assert!(!framed.contains("<rect"));
+    }
+
+    #[test]
+    fn full_width() {
+        let source = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100%" height="2.009ex" style="vertical-align: -0.566ex; min-width: 9.87ex;"></svg>"#;
+        let frame = Frame {
+            background: Some(Color::WHITE),
+            padding: Padding::symmetric(0.5, 1.0),
+            ..Default::default()
+        };
+        let framed = frame.apply(source);
+        assert!(framed.contains(r#" width="100%""#));
+        assert!(framed.contains(
+            r##"><rect x="0" y="0" width="100%" height="100%" fill="#ffffff" fill-opacity="1"></rect><svg y="1.131ex" width="100%" height="2.009ex"></svg></svg>"##
+        ));
+
+        // The vertical space is added as it is for any other render, the horizontal space only to the minimum width
+        let (before, after) = (
+            Metrics::parse(source).unwrap().em(),
+            Metrics::parse(&framed).unwrap().em(),
+        );
+        assert!((after.width - (before.width + 2.0)).abs() < 0.001);
+        assert!((after.height - (before.height + 0.5)).abs() < 0.001);
+        assert!((after.depth - (before.depth + 0.5)).abs() < 0.001);
+    }
+
+    #[test]
+    fn invalid_lengths() {
+        let frame = Frame {
+            padding: Padding {
+                top: f64::NAN,
+                right: -1.0,
+                bottom: f64::INFINITY,
+                left: 0.5,
+            },
+            min_width: f64::NAN,
+            min_height: -2.0,
+            ..Default::default()
+        };
+        let framed = frame.apply(SOURCE);
+        assert!(framed.contains(r#" viewBox="-500 -1442 4150 2139""#));
+    }
+
+    #[test]
+    fn attributes() {
+        let mut tag = String::from(r#"<svg width="1ex" style="color: red; vertical-align: -1ex;""#);
+        set_attribute(&mut tag, "width", "2ex");
+        set_attribute(&mut tag, "height", "3ex");
+        set_style(&mut tag, "vertical-align", "0");
+        set_style(&mut tag, "opacity", "0.5");
+        assert_eq!(
+            tag,
+            r#"<svg width="2ex" style="color: red; vertical-align: 0; opacity: 0.5;" height="3ex""#
+        );
+
+        let mut tag = String::from("<svg");
+        set_style(&mut tag, "color", "red");
+        assert_eq!(tag, r#"<svg style="color: red;""#);
+    }
+}
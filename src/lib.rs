@@ -5,7 +5,13 @@
 //!  - `node` - Enables the [NodeJs](https://nodejs.org/) backend, this will attempt to use a system installation of NodeJs at runtime as the renderer. This will be priotized over any other features if enabled.
 //!  - `browser` - Enables the [`headless_chrome`] backend, this will create a headless Chrome instance to use as the renderer. If this is enabled in conjunction with the `node` feature flag, this will be used as a fall back when NodeJs is not available.
 //!  - `auto` - This is equivelent to enabling all backends (currently just `node` and `browser`) see [`MathJax::new`] for what this specifically does.
-//!  - `image` - Allows converting the rendered SVG into an [`image::DynamicImage`] via [`Render::into_image`].
+//!  - `svg` - Allows finding the part of the expression at a point within a render via [`Render::source_at`], and choosing the fonts of any text via [`FontDatabase`].
+//!  - `image` - Allows converting the rendered SVG into an [`image::DynamicImage`] via [`Render::into_image`] or [`Render::rasterize`], this also enables the `svg` feature.
+//!  - `pdf` - Allows converting the rendered SVG into a PDF document via [`Render::to_pdf`], this also enables the `image` feature.
+//!  - `eps` - Allows converting the rendered SVG into an Encapsulated PostScript figure via [`Render::to_eps`], this also enables the `image` feature.
+//!  - `emf` - Allows converting the rendered SVG into an Enhanced Metafile via [`Render::to_emf`], this also enables the `image` feature.
+//!  - `cli` - Builds the `mathjax-cli` binary, which renders expressions from the command line into SVG, PNG, MathML or PDF. This also enables the `auto` and `image` features.
+//!  - `server` - Builds the `mathjax-server` binary, which serves SVG, PNG and MathML renders over HTTP. This also enables the `auto` and `image` features.
 //!
 //! By default, the `auto` crate feature is enabled.
 //!
@@ -53,12 +59,54 @@
 #[cfg(all(not(feature = "node"), not(feature = "browser")))]
 compile_error!("No renderer enabled, at least one of either the `node` or `browser` feature flags must be enabled.");
 
+#[cfg(feature = "image")]
+mod atlas;
+mod color;
+mod diagnostic;
+mod document;
+#[cfg(feature = "emf")]
+mod emf;
+#[cfg(feature = "eps")]
+mod eps;
 mod error;
+#[cfg(feature = "svg")]
+mod fonts;
+mod frame;
+mod metrics;
+mod mml;
+mod options;
+#[cfg(feature = "pdf")]
+mod pdf;
+#[cfg(feature = "image")]
+mod raster;
 mod renderer;
+#[cfg(feature = "svg")]
+mod source;
+mod style;
+#[cfg(any(feature = "pdf", feature = "eps", feature = "emf"))]
+mod vector;
 
-pub use error::{InitError, RenderError};
-pub use renderer::Render;
-use renderer::Renderer;
+#[cfg(feature = "image")]
+pub use atlas::{Atlas, AtlasOptions, Sprite};
+pub use color::Color;
+pub use diagnostic::{Diagnostic, DiagnosticKind};
+pub use document::{DocumentOptions, DocumentOutput, FoundMath};
+#[cfg(feature = "image")]
+pub use error::RasterError;
+pub use error::{ColorError, InitError, MmlError, RenderError};
+#[cfg(feature = "svg")]
+pub use fonts::FontDatabase;
+pub use frame::{Frame, Padding};
+pub use metrics::{Dimensions, Metrics, ViewBox, X_HEIGHT};
+pub use mml::{walk_mml, MmlElement, MmlKind, MmlNode, MmlVisitor};
+pub use options::{
+    Font, FontCache, IndentAlign, LineBreakMode, LineBreaks, RenderOptions, Speech, TagFormat, Tags,
+};
+#[cfg(feature = "image")]
+pub use raster::{PixelFormat, RasterOptions, RasterSize};
+pub use renderer::{Batch, FontDefs, Label, Render};
+use renderer::{Output, Page, Renderer, Request, Response};
+pub use style::Style;
 
 /// The renderer.
 pub struct MathJax {
@@ -97,18 +145,371 @@ impl MathJax {
         }
     }
 
+    /// Returns the fonts available to render with, see [`RenderOptions::font`].
+    ///
+    /// MathJax v3 (which is the version used by every backend) only includes the [`Font::tex`] font for SVG output.
+    pub fn fonts(&self) -> Vec<Font> {
+        match self.renderer {
+            #[cfg(feature = "node")]
+            Renderer::Node(ref node) => node.fonts(),
+            #[cfg(feature = "browser")]
+            Renderer::Browser(ref browser) => browser.fonts(),
+        }
+    }
+
+    /// Returns whether spoken text can be generated with this renderer, see [`RenderOptions::speech`].
+    ///
+    /// This is only the case for the `browser` backend, which loads the Speech Rule Engine from the MathJax CDN when it is first needed.
+    pub fn speech_available(&self) -> bool {
+        match self.renderer {
+            #[cfg(feature = "node")]
+            Renderer::Node(_) => false,
+            #[cfg(feature = "browser")]
+            Renderer::Browser(_) => true,
+        }
+    }
+
     /// Render the given [MathJax](https://www.mathjax.org/) expression into an image.
     pub fn render<S>(&self, expression: S) -> Result<Render, RenderError>
+    where
+        S: AsRef<str>,
+    {
+        self.render_with(expression, &RenderOptions::default())
+    }
+
+    /// Render the given [MathJax](https://www.mathjax.org/) expression into an image, using the given options.
+    pub fn render_with<S>(
+        &self,
+        expression: S,
+        options: &RenderOptions,
+    ) -> Result<Render, RenderError>
+    where
+        S: AsRef<str>,
+    {
+        let batch = self.render_batch([expression], options)?;
+        // render_batch guarantees exactly one render per expression
+        Ok(batch.into_renders().remove(0))
+    }
+
+    /// Render several [MathJax](https://www.mathjax.org/) expressions together as a single document.
+    ///
+    /// Equation numbering (see [`RenderOptions::tags`]) continues from one expression to the next,
+    /// and `\ref{...}`/`\eqref{...}` can refer to a `\label{...}` anywhere in the batch (including later expressions).
+    ///
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{MathJax, RenderOptions, TagFormat, Tags};
+    ///
+    /// let options = RenderOptions {
+    ///     tags: Tags::All,
+    ///     tag_format: TagFormat {
+    ///         number: String::from("3.{n}"),
+    ///         ..Default::default()
+    ///     },
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let batch = renderer
+    ///     .render_batch([r#"E = mc^2 \label{energy}"#, r#"\text{see } \eqref{energy}"#], &options)
+    ///     .unwrap();
+    /// assert_eq!(batch.labels()["energy"].tag, "3.1");
+    /// assert_eq!(batch.renders().len(), 2);
+    /// # }
+    /// ```
+    pub fn render_batch<I, S>(
+        &self,
+        expressions: I,
+        options: &RenderOptions,
+    ) -> Result<Batch, RenderError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let response = self.request(expressions, options, Output::Svg)?;
+        Ok(Batch::new(response))
+    }
+
+    /// Parses the given [MathJax](https://www.mathjax.org/) expression into its MathML tree, without rendering it.
+    ///
+    /// This is the structure of the expression as MathJax understands it (the internal `MmlTree`), see [`MmlElement`] for an example.
+    pub fn to_mml<S>(&self, expression: S) -> Result<MmlElement, RenderError>
+    where
+        S: AsRef<str>,
+    {
+        self.to_mml_with(expression, &RenderOptions::default())
+    }
+
+    /// Parses the given [MathJax](https://www.mathjax.org/) expression into its MathML tree, using the given options.
+    ///
+    /// Only the options which affect how the expression is parsed (such as [`RenderOptions::tags`]) are used.
+    pub fn to_mml_with<S>(
+        &self,
+        expression: S,
+        options: &RenderOptions,
+    ) -> Result<MmlElement, RenderError>
+    where
+        S: AsRef<str>,
+    {
+        let mut response = self.request([expression], options, Output::MathMl)?;
+        // request guarantees exactly one render per expression
+        response
+            .renders
+            .remove(0)
+            .parse()
+            .map_err(RenderError::MmlError)
+    }
+
+    /// Checks the given [MathJax](https://www.mathjax.org/) expression for problems, without rendering it.
+    ///
+    /// This reports undefined macros, unbalanced braces and environments, unknown environments and missing packages (see [`Diagnostic`]).
+    /// MathJax stops parsing at the first problem, so at most one diagnostic is returned for each expression,
+    /// and an empty list means the expression is valid.
+    ///
+    /// Packages can be loaded with `\require{...}`, but only those already included with MathJax (anything else is reported as a missing package):
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{DiagnosticKind, MathJax};
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// assert!(renderer.validate(r#"\require{cancel} \cancel{x}"#).unwrap().is_empty());
+    ///
+    /// let expression = r#"\require{nonexistent} x"#;
+    /// let diagnostics = renderer.validate(expression).unwrap();
+    /// assert_eq!(diagnostics[0].kind, DiagnosticKind::MissingPackage);
+    /// assert_eq!(&expression[diagnostics[0].span.clone()], r#"\require{nonexistent}"#);
+    /// # }
+    /// ```
+    pub fn validate<S>(&self, expression: S) -> Result<Vec<Diagnostic>, RenderError>
+    where
+        S: AsRef<str>,
+    {
+        self.validate_with(expression, &RenderOptions::default())
+    }
+
+    /// Checks the given [MathJax](https://www.mathjax.org/) expression for problems, using the given options.
+    ///
+    /// Only the options which affect how the expression is parsed (such as [`RenderOptions::tags`], since a label can only be defined once) are used.
+    pub fn validate_with<S>(
+        &self,
+        expression: S,
+        options: &RenderOptions,
+    ) -> Result<Vec<Diagnostic>, RenderError>
     where
         S: AsRef<str>,
     {
         let expression = expression.as_ref();
+        let response = self.request([expression], options, Output::Errors)?;
+        Ok(response
+            .errors
+            .into_iter()
+            .flatten()
+            .map(|error| Diagnostic::new(expression, error.id, error.message, error.offset))
+            .collect())
+    }
 
-        match self.renderer {
+    /// Finds and renders every piece of math in a document (such as Markdown or HTML), returning the document with the math replaced.
+    ///
+    /// Math is found as described by [`DocumentOptions`], and every piece is rendered together as a single batch
+    /// (so equation numbers continue through the document, see [`MathJax::render_batch`]).
+    /// This is the equivalent of the MathJax `tex2svg-page` (or `tex2mml-page`) example:
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{DocumentOptions, DocumentOutput, MathJax};
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let document = r#"<p>Where $x^2$ is \$5:</p> $$\frac{1}{x}$$ <code>$y$</code>"#;
+    ///
+    /// let html = renderer.render_document(document, &DocumentOptions::default()).unwrap();
+    /// assert!(html.starts_with(r#"<p>Where <mjx-container class="MathJax" jax="SVG"><svg"#));
+    /// assert!(html.contains(r#"</svg></mjx-container> is $5:</p>"#));
+    /// assert!(html.contains(r#"<mjx-container class="MathJax" jax="SVG" display="true""#));
+    /// assert!(html.ends_with("<code>$y$</code>"));
+    ///
+    /// let options = DocumentOptions {
+    ///     output: DocumentOutput::MathMl,
+    ///     ..Default::default()
+    /// };
+    /// let html = renderer.render_document(document, &options).unwrap();
+    /// assert!(html.starts_with(r#"<p>Where <math xmlns="http://www.w3.org/1998/Math/MathML"><msup>"#));
+    /// assert!(html.contains(r#"<math xmlns="http://www.w3.org/1998/Math/MathML" display="block"><mfrac>"#));
+    ///
+    /// // HTML character references are decoded (see `DocumentOptions::decode_entities`)
+    /// let html = renderer.render_document("<p>$a &lt; b$</p>", &options).unwrap();
+    /// assert!(html.contains("<mi>a</mi><mo>&lt;</mo><mi>b</mi>"));
+    /// # }
+    /// ```
+    pub fn render_document(
+        &self,
+        document: &str,
+        options: &DocumentOptions,
+    ) -> Result<String, RenderError> {
+        let found = options.find(document);
+        let math: Vec<&FoundMath> = found
+            .iter()
+            .filter_map(|found| match found {
+                document::Found::Math(math) => Some(math),
+                document::Found::Escape(_) => None,
+            })
+            .collect();
+        if math.is_empty() {
+            return Ok(document::substitute(document, &found, []));
+        }
+
+        let expressions: Vec<&str> = math.iter().map(|math| math.tex.as_str()).collect();
+        let display: Vec<bool> = math.iter().map(|math| math.display).collect();
+        let output = match options.output {
+            DocumentOutput::Svg => Output::Svg,
+            DocumentOutput::MathMl => Output::MathMl,
+        };
+        let response = self.send(&Request {
+            expressions: &expressions,
+            display: &display,
+            options: &options.render,
+            output,
+            page: None,
+        })?;
+
+        let replacements: Vec<String> = match options.output {
+            DocumentOutput::Svg => {
+                let batch = Batch::new(response);
+                // Glyphs shared between renders must be defined before the first of them
+                let mut defs = (!batch.font_defs().is_empty()).then(|| batch.font_defs().to_svg());
+                batch
+                    .renders()
+                    .iter()
+                    .zip(&display)
+                    .map(|(render, &display)| {
+                        let container = if display {
+                            r#"<mjx-container class="MathJax" jax="SVG" display="true" style="display: block; text-align: center; margin: 1em 0;">"#
+                        } else {
+                            r#"<mjx-container class="MathJax" jax="SVG">"#
+                        };
+                        format!(
+                            "{}{container}{}</mjx-container>",
+                            defs.take().unwrap_or_default(),
+                            render.as_raw()
+                        )
+                    })
+                    .collect()
+            }
+            DocumentOutput::MathMl => response
+                .renders
+                .iter()
+                .map(|mathml| mathml.parse::<MmlElement>().map(|math| math.to_string()))
+                .collect::<Result<_, _>>()?,
+        };
+        Ok(document::substitute(document, &found, replacements))
+    }
+
+    /// Typesets every piece of math in an HTML page, returning the page with the math pre-rendered as SVG.
+    ///
+    /// This runs the MathJax document handler over the page, as the MathJax `tex2svg-page` example does,
+    /// so the stylesheet the renders need is added to the `<head>` of the page (along with any shared glyphs, see [`FontCache::Global`]).
+    /// Unlike [`MathJax::render_document`] this works on the structure of the page, so math can't span elements,
+    /// and elements with the class `mathjax_ignore` are skipped (unless within an element with the class `mathjax_process`):
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::MathJax;
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let page = r#"<!DOCTYPE html><html><head><title>Math</title></head><body>
+    /// <p>Inline $x^2$ and display $$\frac{1}{2}$$</p>
+    /// <p class="mathjax_ignore">$y$</p>
+    /// <pre>$z$</pre>
+    /// </body></html>"#;
+    ///
+    /// let html = renderer.typeset_html(page).unwrap();
+    /// assert!(html.starts_with("<!DOCTYPE html><html><head><title>Math</title><style"));
+    /// assert_eq!(html.matches("<mjx-container").count(), 2);
+    /// assert!(html.contains(r#"<p class="mathjax_ignore">$y$</p>"#));
+    /// assert!(html.contains("<pre>$z$</pre>"));
+    /// # }
+    /// ```
+    pub fn typeset_html(&self, html: &str) -> Result<String, RenderError> {
+        self.typeset_html_with(html, &DocumentOptions::default())
+    }
+
+    /// Typesets every piece of math in an HTML page, using the given options.
+    ///
+    /// The math is always rendered as SVG (so [`DocumentOptions::output`] is not used),
+    /// and of the [`DocumentOptions::render`] options, only those which affect a whole document (such as [`RenderOptions::tags`] and [`RenderOptions::font`]) are used.
+    pub fn typeset_html_with(
+        &self,
+        html: &str,
+        options: &DocumentOptions,
+    ) -> Result<String, RenderError> {
+        let skip_html_tags = if options.skip_code {
+            document::SKIP_TAGS
+        } else {
+            &[]
+        };
+        let mut response = self.send(&Request {
+            expressions: &[html],
+            display: &[true],
+            options: &options.render,
+            output: Output::Html,
+            page: Some(Page {
+                inline_math: &options.inline_math,
+                display_math: &options.display_math,
+                process_escapes: options.process_escapes,
+                process_environments: options.process_environments,
+                skip_html_tags,
+                ignore_html_class: &options.ignore_html_class,
+                process_html_class: &options.process_html_class,
+            }),
+        })?;
+        // send guarantees exactly one render per expression
+        Ok(response.renders.remove(0))
+    }
+
+    /// Sends the given expressions to the backend, returning a response with exactly one render per expression.
+    fn request<I, S>(
+        &self,
+        expressions: I,
+        options: &RenderOptions,
+        output: Output,
+    ) -> Result<Response, RenderError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let expressions: Vec<S> = expressions.into_iter().collect();
+        let expressions: Vec<&str> = expressions.iter().map(AsRef::as_ref).collect();
+        self.send(&Request {
+            expressions: &expressions,
+            display: &vec![true; expressions.len()],
+            options,
+            output,
+            page: None,
+        })
+    }
+
+    /// Sends a request to the backend, checking there is exactly one render per expression.
+    fn send(&self, request: &Request) -> Result<Response, RenderError> {
+        let options = request.options;
+        if !self.fonts().contains(&options.font) {
+            return Err(RenderError::FontUnavailable(options.font.clone()));
+        }
+
+        let response: Response = match self.renderer {
             #[cfg(feature = "node")]
-            Renderer::Node(ref node) => node.render(expression),
+            Renderer::Node(ref node) => node.render(request)?,
             #[cfg(feature = "browser")]
-            Renderer::Browser(ref browser) => browser.render(expression),
+            Renderer::Browser(ref browser) => browser.render(request)?,
+        };
+
+        if response.renders.len() != request.expressions.len() {
+            let message = format!(
+                "expected {} renders, got {}",
+                request.expressions.len(),
+                response.renders.len()
+            );
+            return Err(RenderError::ProtocolError(serde::de::Error::custom(
+                message,
+            )));
         }
+
+        Ok(response)
     }
 }
@@ -0,0 +1,250 @@
+/// The x-height of the MathJax TeX font, in `em`.
+///
+/// MathJax sizes its output in `ex` using this value, so that it scales with the surrounding text.
+pub const X_HEIGHT: f64 = 0.442;
+
+/// The layout metrics of a [`Render`](crate::Render), see [`Render::metrics`](crate::Render::metrics).
+///
+/// This is everything needed to align a render with the surrounding text, for example:
+/// ```rust
+/// # fn main() {
+/// use mathjax::MathJax;
+///
+/// let renderer = MathJax::new().unwrap();
+/// let render = renderer.render(r#"y=\frac{1}{x}"#).unwrap();
+///
+/// // The size (in pixels) of the render next to 16px text
+/// let size = render.metrics().unwrap().pixels(16.0);
+/// // The render should be placed so that its baseline lines up with the baseline of the text,
+/// //  which is `size.depth` pixels above its bottom edge.
+/// assert!(size.depth > 0.0);
+/// assert_eq!(size.total_height(), size.height + size.depth);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    /// The dimensions in `ex`.
+    ex: Dimensions,
+    /// The `viewBox` of the SVG element.
+    view_box: Option<ViewBox>,
+}
+
+impl Metrics {
+    /// Reads the metrics from the attributes of the root element of the given MathJax SVG.
+    pub(crate) fn parse(source: &str) -> Option<Self> {
+        let document = roxmltree::Document::parse(source).ok()?;
+        let svg = document.root_element();
+
+        let style = |property: &str| {
+            svg.attribute("style")?.split(';').find_map(|declaration| {
+                let (name, value) = declaration.split_once(':')?;
+                (name.trim() == property).then(|| value.trim())
+            })
+        };
+
+        // Equations which stretch to fill their container (e.g. those with a tag) have a percentage width,
+        //  in which case the minimum width is the width of the content.
+        let width = match svg.attribute("width")? {
+            width if width.ends_with('%') => parse_ex(style("min-width")?)?,
+            width => parse_ex(width)?,
+        };
+        let total_height = parse_ex(svg.attribute("height")?)?;
+        // The baseline is aligned by shifting the render down by its depth
+        let depth = -style("vertical-align").map_or(Some(0.0), parse_ex)?;
+
+        let view_box = svg.attribute("viewBox").and_then(|view_box| {
+            let mut values = view_box.split_whitespace().map(str::parse::<f64>);
+            Some(ViewBox {
+                x: values.next()?.ok()?,
+                y: values.next()?.ok()?,
+                width: values.next()?.ok()?,
+                height: values.next()?.ok()?,
+            })
+        });
+
+        Some(Metrics {
+            ex: Dimensions {
+                width,
+                height: total_height - depth,
+                depth,
+            },
+            view_box,
+        })
+    }
+
+    /// Returns the dimensions in `ex` (the x-height of the font), this is the unit the SVG is sized in.
+    pub fn ex(&self) -> Dimensions {
+        self.ex
+    }
+
+    /// Returns the dimensions in `em` (the font size).
+    pub fn em(&self) -> Dimensions {
+        self.ex.scale(X_HEIGHT)
+    }
+
+    /// Returns the dimensions when rendered alongside text of the given font size.
+    ///
+    /// The dimensions are in the same unit as `font_size`, so this will be pixels if `font_size` is in pixels.
+    pub fn pixels(&self, font_size: f64) -> Dimensions {
+        self.em().scale(font_size)
+    }
+
+    /// Returns the `viewBox` of the SVG element.
+    ///
+    /// This will be `None` for equations which stretch to fill their container (e.g. those with a tag), since they don't have a fixed coordinate system.
+    pub fn view_box(&self) -> Option<ViewBox> {
+        self.view_box
+    }
+}
+
+/// The dimensions of a render, all in the same unit.
+///
+/// The baseline divides the render into the [`height`](Dimensions::height) above it,
+/// and the [`depth`](Dimensions::depth) below it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dimensions {
+    /// The width.
+    pub width: f64,
+    /// The height above the baseline, this is also the distance from the top edge down to the baseline.
+    pub height: f64,
+    /// The depth below the baseline, this is also the distance from the bottom edge up to the baseline.
+    pub depth: f64,
+}
+
+impl Dimensions {
+    /// Returns the total height (the [`height`](Dimensions::height) plus the [`depth`](Dimensions::depth)).
+    pub fn total_height(&self) -> f64 {
+        self.height + self.depth
+    }
+
+    /// Returns the CSS `vertical-align` value needed to align the baseline with the surrounding text, this is the negative of the depth.
+    pub fn vertical_align(&self) -> f64 {
+        -self.depth
+    }
+
+    fn scale(&self, factor: f64) -> Self {
+        Dimensions {
+            width: self.width * factor,
+            height: self.height * factor,
+            depth: self.depth * factor,
+        }
+    }
+}
+
+/// The `viewBox` of an SVG element.
+///
+/// MathJax uses thousandths of an `em` as the coordinate system of its SVG output, with the baseline at `y = 0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewBox {
+    /// The minimum x coordinate.
+    pub x: f64,
+    /// The minimum y coordinate.
+    pub y: f64,
+    /// The width.
+    pub width: f64,
+    /// The height.
+    pub height: f64,
+}
+
+/// Parses a length in `ex` (e.g. `-1.577ex`), MathJax writes zero lengths without a unit.
+fn parse_ex(value: &str) -> Option<f64> {
+    value.trim().trim_end_matches("ex").parse().ok()
+}
+
+/// Formats a number the same way MathJax does, rounded to 3 decimal places without any trailing zeros.
+pub(crate) fn format_number(value: f64) -> String {
+    let formatted = format!("{value:.3}");
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+    match formatted {
+        "-0" => String::from("0"),
+        formatted => formatted.to_string(),
+    }
+}
+
+/// Formats a length in `ex`, the inverse of [`parse_ex`].
+pub(crate) fn format_ex(value: f64) -> String {
+    match format_number(value).as_str() {
+        "0" => String::from("0"),
+        value => format!("{value}ex"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let metrics = Metrics::parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="8.258ex" height="4.839ex" role="img" style="vertical-align: -1.577ex;" viewBox="0 -1442 3650 2139"></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            metrics.ex(),
+            Dimensions {
+                width: 8.258,
+                height: 4.839 - 1.577,
+                depth: 1.577,
+            }
+        );
+        assert_eq!(
+            metrics.view_box(),
+            Some(ViewBox {
+                x: 0.0,
+                y: -1442.0,
+                width: 3650.0,
+                height: 2139.0,
+            })
+        );
+        assert_eq!(metrics.pixels(10.0).width, 8.258 * X_HEIGHT * 10.0);
+    }
+
+    #[test]
+    fn parse_without_depth() {
+        let metrics = Metrics::parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="1.294ex" height="1.025ex" viewBox="0 -453 572 453"></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(metrics.ex().depth, 0.0);
+        assert_eq!(metrics.ex().height, 1.025);
+    }
+
+    #[test]
+    fn parse_full_width() {
+        // An equation with a tag stretches to fill its container
+        let metrics = Metrics::parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="100%" height="2.009ex" style="vertical-align: -0.566ex; min-width: 9.87ex;"></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(metrics.ex().width, 9.87);
+        assert_eq!(metrics.ex().depth, 0.566);
+        assert_eq!(metrics.view_box(), None);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(Metrics::parse("<svg"), None);
+        assert_eq!(
+            Metrics::parse(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="100%" height="2ex"/>"#
+            ),
+            None
+        );
+        assert_eq!(
+            Metrics::parse(r#"<svg xmlns="http://www.w3.org/2000/svg" width="2em" height="2ex"/>"#),
+            None
+        );
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_number(1.5), "1.5");
+        assert_eq!(format_number(2.0), "2");
+        assert_eq!(format_number(0.12345), "0.123");
+        assert_eq!(format_number(-0.0001), "0");
+        assert_eq!(format_ex(-1.577), "-1.577ex");
+        assert_eq!(format_ex(0.0004), "0");
+        assert_eq!(parse_ex(&format_ex(-1.577)), Some(-1.577));
+        assert_eq!(parse_ex("0"), Some(0.0));
+    }
+}
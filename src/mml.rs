@@ -0,0 +1,465 @@
+use crate::MmlError;
+use std::collections::BTreeMap;
+
+/// The namespace of every MathML element.
+const NAMESPACE: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// A node within a MathML tree, see [`MathJax::to_mml`](crate::MathJax::to_mml).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MmlNode {
+    /// An element, such as `<mi>` or `<mfrac>`.
+    Element(MmlElement),
+    /// The text content of a token element (e.g. the `x` in `<mi>x</mi>`).
+    Text(String),
+}
+
+impl MmlNode {
+    /// Calls the method of the visitor for this type of node.
+    pub fn accept<V>(&self, visitor: &mut V)
+    where
+        V: MmlVisitor + ?Sized,
+    {
+        match self {
+            MmlNode::Element(element) => visitor.visit_element(element),
+            MmlNode::Text(text) => visitor.visit_text(text),
+        }
+    }
+}
+
+impl std::fmt::Display for MmlNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MmlNode::Element(element) => element.fmt(f),
+            MmlNode::Text(text) => f.write_str(&escape(text, false)),
+        }
+    }
+}
+
+/// A MathML element, this is the structure of an expression as parsed by MathJax (before it is laid out).
+///
+/// Displaying an element produces its MathML, which can be parsed back into the same tree:
+/// ```rust
+/// # fn main() {
+/// use mathjax::{MathJax, MmlElement, MmlKind, MmlNode};
+///
+/// let renderer = MathJax::new().unwrap();
+/// let math = renderer.to_mml(r#"y=\frac{1}{x}"#).unwrap();
+/// assert_eq!(math.kind, MmlKind::Math);
+///
+/// let fraction = math.descendants().find(|element| element.kind == MmlKind::Mfrac).unwrap();
+/// assert_eq!(fraction.children[0], MmlNode::Element(MmlElement {
+///     kind: MmlKind::Mn,
+///     attributes: Default::default(),
+///     children: vec![MmlNode::Text(String::from("1"))],
+/// }));
+///
+/// let mathml = math.to_string();
+/// assert!(mathml.starts_with(r#"<math xmlns="http://www.w3.org/1998/Math/MathML""#));
+/// assert_eq!(mathml.parse::<MmlElement>().unwrap(), math);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MmlElement {
+    /// The type of element.
+    pub kind: MmlKind,
+    /// The attributes of the element, keyed by name.
+    pub attributes: BTreeMap<String, String>,
+    /// The child elements (or for token elements, the text).
+    pub children: Vec<MmlNode>,
+}
+
+impl MmlElement {
+    /// Returns the text content of the element and all of its descendants.
+    pub fn text(&self) -> String {
+        struct Text(String);
+        impl MmlVisitor for Text {
+            fn visit_text(&mut self, text: &str) {
+                self.0.push_str(text);
+            }
+        }
+
+        let mut text = Text(String::new());
+        text.visit_element(self);
+        text.0
+    }
+
+    /// Returns an iterator over the child elements (skipping any text).
+    pub fn elements(&self) -> impl Iterator<Item = &MmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            MmlNode::Element(element) => Some(element),
+            MmlNode::Text(_) => None,
+        })
+    }
+
+    /// Returns an iterator over the element and all of its descendant elements, in document order.
+    pub fn descendants(&self) -> impl Iterator<Item = &MmlElement> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let element = stack.pop()?;
+            // Pushed in reverse, so the first child is visited next
+            stack.extend(
+                element
+                    .children
+                    .iter()
+                    .rev()
+                    .filter_map(|child| match child {
+                        MmlNode::Element(element) => Some(element),
+                        MmlNode::Text(_) => None,
+                    }),
+            );
+            Some(element)
+        })
+    }
+
+    /// Converts a parsed XML element into a MathML element.
+    fn from_xml(node: roxmltree::Node) -> Self {
+        let kind = MmlKind::from(node.tag_name().name());
+        let attributes = node
+            .attributes()
+            .map(|attribute| {
+                // Namespaced attributes (e.g. `xlink:href`) keep their prefix
+                let name = match attribute
+                    .namespace()
+                    .and_then(|uri| node.lookup_prefix(uri))
+                {
+                    Some(prefix) => format!("{prefix}:{}", attribute.name()),
+                    None => attribute.name().to_string(),
+                };
+                (name, attribute.value().to_string())
+            })
+            // As do the declarations of those prefixes, so the MathML can be parsed again
+            .chain(
+                node.namespaces()
+                    .filter(|namespace| {
+                        node.parent_element().is_none_or(|parent| {
+                            !parent.namespaces().any(|other| other == *namespace)
+                        })
+                    })
+                    .filter_map(|namespace| {
+                        let prefix = namespace.name().filter(|&prefix| prefix != "xml")?;
+                        Some((format!("xmlns:{prefix}"), namespace.uri().to_string()))
+                    }),
+            )
+            .collect();
+        // Whitespace between elements is insignificant, but the text of a token is kept as it is
+        //  (it may well be a non-breaking space, e.g. from `a~b`)
+        let children = node
+            .children()
+            .filter_map(|child| match child.node_type() {
+                roxmltree::NodeType::Element => Some(MmlNode::Element(MmlElement::from_xml(child))),
+                roxmltree::NodeType::Text => child
+                    .text()
+                    .filter(|text| {
+                        kind.is_token()
+                            || kind == MmlKind::Annotation
+                            || !text.chars().all(|c| matches!(c, ' ' | '\t' | '\r' | '\n'))
+                    })
+                    .map(|text| MmlNode::Text(text.to_string())),
+                _ => None,
+            })
+            .collect();
+
+        MmlElement {
+            kind,
+            attributes,
+            children,
+        }
+    }
+}
+
+impl std::str::FromStr for MmlElement {
+    type Err = MmlError;
+
+    /// Parses a MathML element, such as the output of its [`Display`](std::fmt::Display) implementation.
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let document =
+            roxmltree::Document::parse(source).map_err(|err| MmlError(err.to_string()))?;
+        Ok(MmlElement::from_xml(document.root_element()))
+    }
+}
+
+impl std::fmt::Display for MmlElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self.kind.name();
+        write!(f, "<{name}")?;
+        if self.kind == MmlKind::Math {
+            write!(f, r#" xmlns="{NAMESPACE}""#)?;
+        }
+        for (attribute, value) in &self.attributes {
+            write!(f, r#" {attribute}="{}""#, escape(value, true))?;
+        }
+        f.write_str(">")?;
+        for child in &self.children {
+            child.fmt(f)?;
+        }
+        write!(f, "</{name}>")
+    }
+}
+
+/// Escapes the characters which can't appear in XML text (or attribute values).
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The type of a MathML element.
+///
+/// Every element MathJax produces has its own variant, anything else is kept as [`MmlKind::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(from = "String", into = "String")]
+#[allow(missing_docs)]
+pub enum MmlKind {
+    Math,
+    Mi,
+    Mn,
+    Mo,
+    Mtext,
+    Mspace,
+    Ms,
+    Mglyph,
+    Mrow,
+    Mfrac,
+    Msqrt,
+    Mroot,
+    Mstyle,
+    Merror,
+    Mpadded,
+    Mphantom,
+    Menclose,
+    Msub,
+    Msup,
+    Msubsup,
+    Munder,
+    Mover,
+    Munderover,
+    Mmultiscripts,
+    Mprescripts,
+    None,
+    Mtable,
+    Mtr,
+    Mlabeledtr,
+    Mtd,
+    Maction,
+    Semantics,
+    Annotation,
+    AnnotationXml,
+    /// Any other element, with the given name.
+    Other(String),
+}
+
+/// The name of each element (other than [`MmlKind::Other`]).
+const KINDS: &[(MmlKind, &str)] = &[
+    (MmlKind::Math, "math"),
+    (MmlKind::Mi, "mi"),
+    (MmlKind::Mn, "mn"),
+    (MmlKind::Mo, "mo"),
+    (MmlKind::Mtext, "mtext"),
+    (MmlKind::Mspace, "mspace"),
+    (MmlKind::Ms, "ms"),
+    (MmlKind::Mglyph, "mglyph"),
+    (MmlKind::Mrow, "mrow"),
+    (MmlKind::Mfrac, "mfrac"),
+    (MmlKind::Msqrt, "msqrt"),
+    (MmlKind::Mroot, "mroot"),
+    (MmlKind::Mstyle, "mstyle"),
+    (MmlKind::Merror, "merror"),
+    (MmlKind::Mpadded, "mpadded"),
+    (MmlKind::Mphantom, "mphantom"),
+    (MmlKind::Menclose, "menclose"),
+    (MmlKind::Msub, "msub"),
+    (MmlKind::Msup, "msup"),
+    (MmlKind::Msubsup, "msubsup"),
+    (MmlKind::Munder, "munder"),
+    (MmlKind::Mover, "mover"),
+    (MmlKind::Munderover, "munderover"),
+    (MmlKind::Mmultiscripts, "mmultiscripts"),
+    (MmlKind::Mprescripts, "mprescripts"),
+    (MmlKind::None, "none"),
+    (MmlKind::Mtable, "mtable"),
+    (MmlKind::Mtr, "mtr"),
+    (MmlKind::Mlabeledtr, "mlabeledtr"),
+    (MmlKind::Mtd, "mtd"),
+    (MmlKind::Maction, "maction"),
+    (MmlKind::Semantics, "semantics"),
+    (MmlKind::Annotation, "annotation"),
+    (MmlKind::AnnotationXml, "annotation-xml"),
+];
+
+impl MmlKind {
+    /// Returns the name of the element, e.g. `mfrac`.
+    pub fn name(&self) -> &str {
+        match self {
+            MmlKind::Other(name) => name,
+            kind => KINDS
+                .iter()
+                .find_map(|(other, name)| (other == kind).then_some(*name))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns whether this is a token element, which contains text rather than other elements.
+    pub fn is_token(&self) -> bool {
+        matches!(
+            self,
+            MmlKind::Mi | MmlKind::Mn | MmlKind::Mo | MmlKind::Mtext | MmlKind::Ms
+        )
+    }
+}
+
+impl From<&str> for MmlKind {
+    fn from(name: &str) -> Self {
+        KINDS
+            .iter()
+            .find_map(|(kind, other)| (*other == name).then(|| kind.clone()))
+            .unwrap_or_else(|| MmlKind::Other(name.to_string()))
+    }
+}
+
+impl From<String> for MmlKind {
+    fn from(name: String) -> Self {
+        MmlKind::from(name.as_str())
+    }
+}
+
+impl From<MmlKind> for String {
+    fn from(kind: MmlKind) -> Self {
+        kind.name().to_string()
+    }
+}
+
+impl std::fmt::Display for MmlKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Walks a MathML tree, see [`MmlNode::accept`].
+///
+/// By default every element is visited in document order, so only the methods for the nodes of interest need to be implemented.
+/// For example, to collect every identifier in an expression:
+/// ```rust
+/// # fn main() {
+/// use mathjax::{MathJax, MmlElement, MmlKind, MmlVisitor};
+///
+/// struct Identifiers(Vec<String>);
+///
+/// impl MmlVisitor for Identifiers {
+///     fn visit_element(&mut self, element: &MmlElement) {
+///         if element.kind == MmlKind::Mi {
+///             self.0.push(element.text());
+///         }
+///         mathjax::walk_mml(self, element);
+///     }
+/// }
+///
+/// let renderer = MathJax::new().unwrap();
+/// let math = renderer.to_mml(r#"y=\frac{1}{x}"#).unwrap();
+/// let mut identifiers = Identifiers(Vec::new());
+/// identifiers.visit_element(&math);
+/// assert_eq!(identifiers.0, ["y", "x"]);
+/// # }
+/// ```
+pub trait MmlVisitor {
+    /// Visits an element, by default this visits each of its children (see [`walk_mml`]).
+    fn visit_element(&mut self, element: &MmlElement) {
+        walk_mml(self, element);
+    }
+
+    /// Visits the text content of a token element, by default this does nothing.
+    fn visit_text(&mut self, _text: &str) {}
+}
+
+/// Visits each child of the given element, this is the default behaviour of [`MmlVisitor::visit_element`].
+pub fn walk_mml<V>(visitor: &mut V, element: &MmlElement)
+where
+    V: MmlVisitor + ?Sized,
+{
+    for child in &element.children {
+        child.accept(visitor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(kind: MmlKind, text: &str) -> MmlNode {
+        MmlNode::Element(MmlElement {
+            kind,
+            attributes: BTreeMap::new(),
+            children: vec![MmlNode::Text(text.to_string())],
+        })
+    }
+
+    #[test]
+    fn whitespace_between_elements() {
+        let math: MmlElement = "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">\n  <mrow>\n    <mi>x</mi>\n  </mrow>\n</math>"
+            .parse()
+            .unwrap();
+        assert_eq!(math.children.len(), 1);
+        assert_eq!(
+            math.elements().next().unwrap().children,
+            [token(MmlKind::Mi, "x")]
+        );
+    }
+
+    #[test]
+    fn token_text_is_kept() {
+        // The MathML of `a~b` and `\text{ a b }`
+        let source =
+            "<math><mi>a</mi><mtext>&#xA0;</mtext><mi>b</mi><mtext>&#xA0;a  b&#xA0;</mtext></math>";
+        let math: MmlElement = source.parse().unwrap();
+        assert_eq!(
+            math.children,
+            [
+                token(MmlKind::Mi, "a"),
+                token(MmlKind::Mtext, "\u{A0}"),
+                token(MmlKind::Mi, "b"),
+                token(MmlKind::Mtext, "\u{A0}a  b\u{A0}"),
+            ]
+        );
+        assert_eq!(math.to_string().parse::<MmlElement>().unwrap(), math);
+    }
+
+    #[test]
+    fn namespaced_attributes() {
+        let source = r#"<math xmlns="http://www.w3.org/1998/Math/MathML" xmlns:xlink="http://www.w3.org/1999/xlink"><mrow xlink:href="https://example.com" xml:lang="en"><mi>x</mi></mrow></math>"#;
+        let math: MmlElement = source.parse().unwrap();
+        let row = math.elements().next().unwrap();
+        assert_eq!(row.attributes["xlink:href"], "https://example.com");
+        assert_eq!(row.attributes["xml:lang"], "en");
+        assert_eq!(math.to_string(), source);
+        assert_eq!(math.to_string().parse::<MmlElement>().unwrap(), math);
+    }
+
+    #[test]
+    fn display_escapes() {
+        let mut math = MmlElement {
+            kind: MmlKind::Math,
+            attributes: BTreeMap::new(),
+            children: vec![token(MmlKind::Mo, "<&>")],
+        };
+        math.attributes
+            .insert(String::from("alttext"), String::from(r#"a "<" b"#));
+        assert_eq!(
+            math.to_string(),
+            r#"<math xmlns="http://www.w3.org/1998/Math/MathML" alttext="a &quot;&lt;&quot; b"><mo>&lt;&amp;&gt;</mo></math>"#
+        );
+        assert_eq!(math.to_string().parse::<MmlElement>().unwrap(), math);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("<math><mi>x</math>".parse::<MmlElement>().is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use serde::Serialize;
+
+/// Options which control how expressions are rendered, see [`MathJax::render_with`](crate::MathJax::render_with).
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderOptions {
+    /// Which equations are automatically numbered.
+    pub tags: Tags,
+    /// How equation numbers are formatted.
+    pub tag_format: TagFormat,
+    /// How long equations are broken over multiple lines.
+    pub line_breaks: LineBreaks,
+    /// The font used for the rendered output.
+    pub font: Font,
+    /// How glyph outlines are shared between SVG elements.
+    pub font_cache: FontCache,
+    /// Whether spoken text is generated for each expression, for use by screen readers.
+    ///
+    /// This is only supported by the `browser` backend, see [`MathJax::speech_available`](crate::MathJax::speech_available).
+    pub speech: Speech,
+    /// Whether the MathML of each expression is embedded (hidden) in the SVG, for use by screen readers.
+    ///
+    /// This is the equivalent of the MathJax `a11y/assistive-mml` extension: the MathML is placed in a zero sized `<foreignObject>`
+    /// at the end of the SVG, and the drawing itself is hidden from screen readers (with `aria-hidden`).
+    /// It doesn't change how the render looks:
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{MathJax, RenderOptions};
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let expression = r#"y=\frac{1}{x}"#;
+    /// let options = RenderOptions {
+    ///     assistive_mml: true,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let accessible = renderer.render_with(expression, &options).unwrap();
+    /// assert!(accessible.as_raw().contains("<mfrac>"));
+    ///
+    /// let plain = renderer.render(expression).unwrap();
+    /// assert_eq!(
+    ///     accessible.into_image(5.0).unwrap().into_bytes(),
+    ///     plain.into_image(5.0).unwrap().into_bytes(),
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// Defaults to `false`.
+    pub assistive_mml: bool,
+    /// Whether each element of the SVG is annotated with the part of the expression it was produced from.
+    ///
+    /// Each `<g>` element is given a `data-mjx-src="start:end"` attribute, the span (as byte offsets) of the expression,
+    /// and [`Render::source_at`](crate::Render::source_at) (with the `svg` feature) finds the span at a point within the render.
+    /// Spans aren't added to expressions which are broken over multiple lines (see [`RenderOptions::line_breaks`]).
+    ///
+    /// Defaults to `false`.
+    pub source_spans: bool,
+}
+
+/// Which equations are automatically numbered, this corresponds to the MathJax `tags` option.
+///
+/// Equations can always be numbered manually with `\tag{...}`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tags {
+    /// No equations are automatically numbered.
+    #[default]
+    None,
+    /// Only numbered environments from the AMS math package (e.g. `align` or `equation`) are numbered.
+    Ams,
+    /// All display equations are numbered.
+    All,
+}
+
+/// The format of equation numbers, this is the equivalent of the MathJax `tagformat` package.
+///
+/// Each field is a template where the given placeholder is substituted for the corresponding value.
+/// For example, to number equations as `(3.1)`, `(3.2)`, ... within the third section of a document:
+/// ```rust
+/// use mathjax::TagFormat;
+///
+/// let format = TagFormat {
+///     number: String::from("3.{n}"),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagFormat {
+    /// The equation number, `{n}` is replaced with the value of the equation counter.
+    ///
+    /// Defaults to `{n}`.
+    pub number: String,
+    /// The tag displayed alongside the equation (and by `\eqref`), `{tag}` is replaced with the equation number.
+    ///
+    /// Defaults to `({tag})`.
+    pub tag: String,
+    /// The `id` attribute given to the equation, `{id}` is replaced with the label (or equation number if there is no label).
+    ///
+    /// Defaults to `mjx-eqn:{id}`.
+    pub id: String,
+}
+
+impl Default for TagFormat {
+    fn default() -> Self {
+        TagFormat {
+            number: String::from("{n}"),
+            tag: String::from("({tag})"),
+            id: String::from("mjx-eqn:{id}"),
+        }
+    }
+}
+
+/// Controls how long equations are broken over multiple lines.
+///
+/// Lines are only ever broken at the top level of an expression (never within a group, environment or `\left...\right` pair),
+/// and only before a relation (e.g. `=`) or a binary operator (e.g. `+`).
+/// The lines are then stacked within an `aligned` (or `gathered`, for [`IndentAlign::Center`]) environment.
+///
+/// For example, to fit equations within a container `20em` wide:
+/// ```rust
+/// use mathjax::{LineBreakMode, LineBreaks};
+///
+/// let line_breaks = LineBreaks {
+///     mode: LineBreakMode::Automatic,
+///     width: 20.0,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineBreaks {
+    /// When lines are broken.
+    ///
+    /// Defaults to [`LineBreakMode::Off`].
+    pub mode: LineBreakMode,
+    /// The width (in `em`, i.e. multiples of the font size) of the container the lines should fit within.
+    /// This is only used by [`LineBreakMode::Automatic`].
+    ///
+    /// Defaults to `40.0`.
+    pub width: f64,
+    /// How the lines are aligned with one another.
+    ///
+    /// Defaults to [`IndentAlign::Left`].
+    pub indent_align: IndentAlign,
+    /// How far (in `em`) every line after the first is indented.
+    ///
+    /// Defaults to `0.0`.
+    pub indent_shift: f64,
+}
+
+impl Default for LineBreaks {
+    fn default() -> Self {
+        LineBreaks {
+            mode: LineBreakMode::Off,
+            width: 40.0,
+            indent_align: IndentAlign::Left,
+            indent_shift: 0.0,
+        }
+    }
+}
+
+/// When lines are broken, see [`LineBreaks`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineBreakMode {
+    /// Lines are never broken, this is the default MathJax behaviour.
+    #[default]
+    Off,
+    /// Lines are only broken at an explicit `\\` or `\newline`.
+    Manual,
+    /// Lines are broken at an explicit `\\` or `\newline`, and wherever needed to fit within [`LineBreaks::width`].
+    Automatic,
+}
+
+/// How the lines of a broken equation are aligned with one another, see [`LineBreaks`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentAlign {
+    /// Lines are aligned on their left edge.
+    #[default]
+    Left,
+    /// Lines are centered.
+    Center,
+    /// Lines are aligned on their right edge.
+    Right,
+}
+
+/// A font used by MathJax for SVG output.
+///
+/// The fonts which can be used depend on the installed version of MathJax, see [`MathJax::fonts`](crate::MathJax::fonts).
+/// Rendering with a font that is not available will fail with [`RenderError::FontUnavailable`](crate::RenderError::FontUnavailable).
+///
+/// Defaults to [`Font::tex`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Font(String);
+
+impl Font {
+    /// Creates a font with the given name, this is the name of the module within MathJax's `output/svg/fonts` directory (e.g. `tex`).
+    pub fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Font(name.into())
+    }
+
+    /// The default MathJax TeX font, this is always available.
+    pub fn tex() -> Self {
+        Font::new("tex")
+    }
+
+    /// Returns the name of the font.
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Font::tex()
+    }
+}
+
+impl std::fmt::Display for Font {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// How glyph outlines are shared between SVG elements, this corresponds to the MathJax `fontCache` option.
+///
+/// This only changes how the SVG is structured, every mode produces the same image:
+/// ```rust
+/// # fn main() {
+/// use mathjax::{FontCache, MathJax, RenderOptions};
+///
+/// let renderer = MathJax::new().unwrap();
+/// let expression = r#"y=\frac{1}{x}"#;
+///
+/// let renders = [FontCache::Local, FontCache::Global, FontCache::None].map(|font_cache| {
+///     let options = RenderOptions {
+///         font_cache,
+///         ..Default::default()
+///     };
+///     renderer.render_with(expression, &options).unwrap()
+/// });
+/// assert!(!renders[2].as_raw().contains("<use"));
+///
+/// let pixels = renders.map(|render| render.into_image(5.0).unwrap().into_bytes());
+/// assert_eq!(pixels[0], pixels[1]);
+/// assert_eq!(pixels[0], pixels[2]);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontCache {
+    /// Each render contains a `<defs>` element with the outline of every glyph it uses, so it can be displayed on its own.
+    #[default]
+    Local,
+    /// The glyph outlines are collected into a single set of definitions shared by every render in a batch
+    /// (see [`Batch::font_defs`](crate::Batch::font_defs)), which must be included once in the page the renders are displayed on.
+    ///
+    /// This avoids repeating the same outlines in every render, which greatly reduces the size of pages with many formulas.
+    Global,
+    /// Every glyph is drawn with its own `<path>`, without any `<defs>` or `<use>` elements.
+    ///
+    /// This produces larger output, but is supported by tools which can't resolve `<use xlink:href="...">` references (e.g. some PDF generators and plotters).
+    None,
+}
+
+/// The rules used to generate spoken text for each expression, see [`Render::speech`](crate::Render::speech).
+///
+/// The text is generated by the [Speech Rule Engine](https://github.com/zorkow/speech-rule-engine),
+/// and is also attached to the SVG as its `aria-label` and `<title>`, so the render is described to screen readers and in accessibility audits.
+///
+/// The Speech Rule Engine is only available with the `browser` backend (which loads it from the MathJax CDN, so needs network access).
+/// The MathJax data bundled for the NodeJs backend doesn't include it, so rendering with speech enabled there
+/// fails with [`RenderError::SpeechUnavailable`](crate::RenderError::SpeechUnavailable).
+/// Use [`MathJax::speech_available`](crate::MathJax::speech_available) to check before requesting speech:
+/// ```rust
+/// # fn main() {
+/// use mathjax::{MathJax, RenderError, RenderOptions, Speech};
+///
+/// let renderer = MathJax::new().unwrap();
+/// let options = RenderOptions {
+///     speech: Speech::ClearSpeak,
+///     ..Default::default()
+/// };
+/// match renderer.render_with("x^2", &options) {
+///     Ok(render) => assert!(renderer.speech_available() && render.speech().is_some()),
+///     Err(RenderError::SpeechUnavailable) => assert!(!renderer.speech_available()),
+///     Err(err) => panic!("{err}"),
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Speech {
+    /// No spoken text is generated, this is the default MathJax behaviour.
+    #[default]
+    Off,
+    /// The ClearSpeak rules, which read expressions the way they would be spoken by a teacher (e.g. "x squared").
+    ClearSpeak,
+    /// The MathSpeak rules, which read expressions unambiguously (e.g. "x super 2 baseline").
+    MathSpeak,
+}
@@ -0,0 +1,148 @@
+use crate::Render;
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref};
+
+impl Render {
+    /// Converts the render into a single page PDF document, cropped tightly to the render.
+    ///
+    /// The formula is drawn with vector paths (so it stays sharp at any zoom level) and sized to match text of the given font size (in points).
+    /// Any text (e.g. from `\text{...}`) is drawn with the system fonts, see [`Render::to_pdf_with`] to use other fonts.
+    /// For example, to include a formula in a document typeset in `11pt` text:
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::MathJax;
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let render = renderer.render(r#"y=\frac{1}{x}"#).unwrap();
+    /// let pdf = render.to_pdf(11.0).unwrap();
+    ///
+    /// assert!(pdf.starts_with(b"%PDF-"));
+    /// # }
+    /// ```
+    pub fn to_pdf(&self, font_size: f64) -> Result<Vec<u8>, crate::RasterError> {
+        self.to_pdf_with(font_size, &crate::FontDatabase::System)
+    }
+
+    /// Converts the render into an PDF document (as [`Render::to_pdf`]), any text is drawn with the given fonts.
+    pub fn to_pdf_with(
+        &self,
+        font_size: f64,
+        fonts: &crate::FontDatabase,
+    ) -> Result<Vec<u8>, crate::RasterError> {
+        let drawing = self.to_drawing(font_size, fonts)?;
+
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let page_id = Ref::new(3);
+        let content_id = Ref::new(4);
+        let mut next_id = 5;
+
+        // PDF coordinates start at the bottom left, with the y axis pointing up
+        let mut content = Content::new();
+        content.transform([1.0, 0.0, 0.0, -1.0, 0.0, drawing.height as f32]);
+
+        // Each distinct opacity needs its own graphics state
+        let mut states: Vec<(String, Ref, f32, f32)> = Vec::new();
+        for shape in &drawing.shapes {
+            let fill_opacity = shape.fill.as_ref().map_or(1.0, |fill| fill.opacity) as f32;
+            let stroke_opacity = shape.stroke.as_ref().map_or(1.0, |stroke| stroke.opacity) as f32;
+            content.save_state();
+            if fill_opacity < 1.0 || stroke_opacity < 1.0 {
+                let position = states
+                    .iter()
+                    .position(|&(_, _, fill, stroke)| {
+                        fill == fill_opacity && stroke == stroke_opacity
+                    })
+                    .unwrap_or_else(|| {
+                        states.push((
+                            format!("GS{}", states.len()),
+                            Ref::new(next_id),
+                            fill_opacity,
+                            stroke_opacity,
+                        ));
+                        next_id += 1;
+                        states.len() - 1
+                    });
+                content.set_parameters(Name(states[position].0.as_bytes()));
+            }
+
+            for segment in &shape.segments {
+                match *segment {
+                    resvg::usvg::PathSegment::MoveTo { x, y } => {
+                        content.move_to(x as f32, y as f32);
+                    }
+                    resvg::usvg::PathSegment::LineTo { x, y } => {
+                        content.line_to(x as f32, y as f32);
+                    }
+                    resvg::usvg::PathSegment::CurveTo {
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        x,
+                        y,
+                    } => {
+                        content.cubic_to(
+                            x1 as f32, y1 as f32, x2 as f32, y2 as f32, x as f32, y as f32,
+                        );
+                    }
+                    resvg::usvg::PathSegment::ClosePath => {
+                        content.close_path();
+                    }
+                }
+            }
+
+            if let Some(ref stroke) = shape.stroke {
+                let [red, green, blue] = stroke.color.map(|channel| channel as f32 / 255.0);
+                content.set_stroke_rgb(red, green, blue);
+                content.set_line_width(stroke.width as f32);
+            }
+            match (&shape.fill, &shape.stroke) {
+                (Some(fill), stroke) => {
+                    let [red, green, blue] = fill.color.map(|channel| channel as f32 / 255.0);
+                    content.set_fill_rgb(red, green, blue);
+                    match (fill.even_odd, stroke.is_some()) {
+                        (false, false) => content.fill_nonzero(),
+                        (true, false) => content.fill_even_odd(),
+                        (false, true) => content.fill_nonzero_and_stroke(),
+                        (true, true) => content.fill_even_odd_and_stroke(),
+                    };
+                }
+                (None, _) => {
+                    content.stroke();
+                }
+            }
+            content.restore_state();
+        }
+        let content = miniz_oxide::deflate::compress_to_vec_zlib(&content.finish(), 6);
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id).kids([page_id]).count(1);
+        let mut page = pdf.page(page_id);
+        page.parent(page_tree_id)
+            .media_box(Rect::new(
+                0.0,
+                0.0,
+                drawing.width as f32,
+                drawing.height as f32,
+            ))
+            .contents(content_id);
+        let mut resources = page.resources();
+        let mut ext_g_states = resources.ext_g_states();
+        for (name, id, _, _) in &states {
+            ext_g_states.pair(Name(name.as_bytes()), *id);
+        }
+        ext_g_states.finish();
+        resources.finish();
+        page.finish();
+
+        pdf.stream(content_id, &content).filter(Filter::FlateDecode);
+        for (_, id, fill, stroke) in states {
+            pdf.ext_graphics(id)
+                .non_stroking_alpha(fill)
+                .stroking_alpha(stroke);
+        }
+
+        Ok(pdf.finish())
+    }
+}
@@ -0,0 +1,615 @@
+use crate::{FontDatabase, RasterError, Render};
+
+/// Options which control how a [`Render`] is rasterized, see [`Render::rasterize`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RasterOptions {
+    /// The size of the output image.
+    pub size: RasterSize,
+    /// The frame drawn around the image, this is in addition to any frame set with [`Render::set_frame`].
+    ///
+    /// Defaults to an empty frame (the image is cropped tightly to the render, with a transparent background).
+    pub frame: crate::Frame,
+    /// The fonts used to draw any text which MathJax doesn't draw with its own glyphs.
+    ///
+    /// Defaults to [`FontDatabase::System`].
+    pub fonts: FontDatabase,
+}
+
+/// The size of a rasterized image.
+///
+/// The aspect ratio of the render is always preserved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RasterSize {
+    /// Scale the intrinsic size of the SVG by the given factor, see [`Render::into_image`].
+    Scale(f32),
+    /// Size the image to match text of the given font size (in points) at the given resolution (in dots per inch).
+    ///
+    /// This is the default, with a `12pt` font at `96` DPI.
+    FontSize {
+        /// The font size in points (`1/72` of an inch).
+        points: f32,
+        /// The resolution in dots per inch.
+        dpi: f32,
+    },
+    /// Make the image exactly the given width (in pixels).
+    Width(u32),
+    /// Make the image exactly the given height (in pixels).
+    Height(u32),
+    /// Make the image as large as possible while still fitting within the given width and height (in pixels).
+    Fit {
+        /// The maximum width.
+        width: u32,
+        /// The maximum height.
+        height: u32,
+    },
+}
+
+impl Default for RasterSize {
+    fn default() -> Self {
+        RasterSize::FontSize {
+            points: 12.0,
+            dpi: 96.0,
+        }
+    }
+}
+
+/// The layout of each pixel in a buffer, see [`Render::rasterize_into`].
+///
+/// Every format has 8 bits per channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Red, green, blue and alpha, with the color channels multiplied by the alpha.
+    ///
+    /// This is the native format of the rasterizer (and most GPU blending), so it needs no conversion.
+    RgbaPremultiplied,
+    /// Red, green, blue and alpha, with the color channels independent of the alpha.
+    Rgba,
+    /// Blue, green, red and alpha, with the color channels multiplied by the alpha.
+    BgraPremultiplied,
+    /// Blue, green, red and alpha, with the color channels independent of the alpha.
+    Bgra,
+}
+
+impl PixelFormat {
+    fn is_premultiplied(self) -> bool {
+        matches!(
+            self,
+            PixelFormat::RgbaPremultiplied | PixelFormat::BgraPremultiplied
+        )
+    }
+
+    fn is_bgra(self) -> bool {
+        matches!(self, PixelFormat::BgraPremultiplied | PixelFormat::Bgra)
+    }
+}
+
+impl Render {
+    /// Rasterizes the render into an [`image::DynamicImage`] with the given options.
+    ///
+    /// For example, to render an expression at the same size as `11pt` text in a `300` DPI document:
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{MathJax, RasterOptions, RasterSize};
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let render = renderer.render(r#"y=\frac{1}{x}"#).unwrap();
+    /// let options = RasterOptions {
+    ///     size: RasterSize::FontSize {
+    ///         points: 11.0,
+    ///         dpi: 300.0,
+    ///     },
+    ///     ..Default::default()
+    /// };
+    /// let image = render.rasterize(&options).unwrap();
+    ///
+    /// // An 11pt font at 300 DPI is ~45.8px, so the image is as tall as the render is in `em` at that font size
+    /// let height = render.metrics().unwrap().pixels(11.0 * 300.0 / 72.0).total_height();
+    /// assert_eq!(image.height(), height.ceil() as u32);
+    /// # }
+    /// ```
+    ///
+    /// A frame can be used to give the image an opaque background (and some padding), e.g. for viewers which ignore transparency:
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{Color, Frame, MathJax, Padding, RasterOptions};
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let render = renderer.render(r#"y=\frac{1}{x}"#).unwrap();
+    /// let options = RasterOptions {
+    ///     frame: Frame {
+    ///         background: Some(Color::WHITE),
+    ///         padding: Padding::uniform(0.25),
+    ///         ..Default::default()
+    ///     },
+    ///     ..Default::default()
+    /// };
+    /// let image = render.rasterize(&options).unwrap().into_rgba8();
+    ///
+    /// assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+    /// # }
+    /// ```
+    pub fn rasterize(&self, options: &RasterOptions) -> Result<image::DynamicImage, RasterError> {
+        let pixmap = self.to_pixmap(options)?;
+        Ok(image::DynamicImage::ImageRgba8(into_rgba(pixmap)?))
+    }
+
+    /// Parses the render with the given options, ready to be drawn.
+    pub(crate) fn prepare(&self, options: &RasterOptions) -> Result<Prepared, RasterError> {
+        let source = self.standalone_source();
+        let source = options.frame.apply(&source);
+        let rtree = crate::renderer::parse_svg(&source, &options.fonts)?;
+        let size = rtree.size;
+        let metrics = crate::Metrics::parse(&source);
+
+        let (scale, pixmap_size) = match options.size {
+            // This matches how the intrinsic size has always been scaled by `Render::into_image`
+            RasterSize::Scale(factor) => (
+                factor as f64,
+                resvg::IntSize::from_usvg(size).scale_by(factor.into()),
+            ),
+            target => {
+                let scale = match target {
+                    RasterSize::FontSize { points, dpi } => {
+                        let font_size = points as f64 * dpi as f64 / 72.0;
+                        let metrics = metrics.ok_or(RasterError::MissingMetrics)?;
+                        metrics.pixels(font_size).total_height() / size.height()
+                    }
+                    RasterSize::Width(width) => width as f64 / size.width(),
+                    RasterSize::Height(height) => height as f64 / size.height(),
+                    RasterSize::Fit { width, height } => {
+                        f64::min(width as f64 / size.width(), height as f64 / size.height())
+                    }
+                    RasterSize::Scale(_) => unreachable!(),
+                };
+                let (width, height) = scaled_size(size.width(), size.height(), scale, target);
+                (scale, resvg::IntSize::new(width, height))
+            }
+        };
+
+        let invalid_size = || RasterError::InvalidSize {
+            width: size.width() * scale,
+            height: size.height() * scale,
+        };
+        let pixmap_size = pixmap_size.ok_or_else(invalid_size)?;
+
+        Ok(Prepared {
+            tree: rtree,
+            scale,
+            size: pixmap_size,
+            baseline: metrics.map(|metrics| {
+                let em = metrics.em();
+                em.height / em.total_height() * size.height() * scale
+            }),
+        })
+    }
+
+    /// Rasterizes the render into a [`resvg::tiny_skia::Pixmap`] with the given options.
+    ///
+    /// This is the raw output of the rasterizer (in premultiplied RGBA), every other raster output is produced from this.
+    pub fn to_pixmap(
+        &self,
+        options: &RasterOptions,
+    ) -> Result<resvg::tiny_skia::Pixmap, RasterError> {
+        self.prepare(options)?.render()
+    }
+
+    /// Returns the size (in pixels) of the image the render is rasterized into with the given options.
+    ///
+    /// This is useful to allocate a buffer for [`Render::rasterize_into`].
+    pub fn raster_size(&self, options: &RasterOptions) -> Result<(u32, u32), RasterError> {
+        let size = self.prepare(options)?.size;
+        Ok((size.width(), size.height()))
+    }
+
+    /// Rasterizes the render with the given options directly into a buffer of pixels, in the given format.
+    ///
+    /// The buffer holds an image of `width` by `height` pixels, with each row starting `stride` bytes after the previous one.
+    /// The render is drawn in the top left corner of the image (at the size from [`Render::raster_size`]) and clipped to it,
+    /// and every other pixel of the image is cleared to transparent.
+    /// Bytes between the end of a row and the start of the next (when `stride` is more than `width * 4`) are left untouched,
+    /// so the image can be a region of a larger buffer.
+    ///
+    /// When the rows are tightly packed (`stride` is exactly `width * 4`) the render is drawn straight into the buffer without allocating,
+    /// otherwise it is drawn into a temporary image which is then copied into the buffer row by row.
+    ///
+    /// For example, to upload a formula as a texture:
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{MathJax, PixelFormat, RasterOptions};
+    ///
+    /// let renderer = MathJax::new().unwrap();
+    /// let render = renderer.render(r#"y=\frac{1}{x}"#).unwrap();
+    /// let options = RasterOptions::default();
+    ///
+    /// let (width, height) = render.raster_size(&options).unwrap();
+    /// let mut texture = vec![0; width as usize * height as usize * 4];
+    /// let stride = width as usize * 4;
+    /// render
+    ///     .rasterize_into(&options, &mut texture, width, height, stride, PixelFormat::BgraPremultiplied)
+    ///     .unwrap();
+    ///
+    /// // This is the same image as `Render::rasterize` produces, with the channels reordered
+    /// let image = render.rasterize(&options).unwrap().into_rgba8();
+    /// let opaque = image.pixels().position(|pixel| pixel.0[3] == 255).unwrap();
+    /// assert_eq!(texture[opaque * 4 + 3], 255);
+    /// assert_eq!(texture[opaque * 4], image.as_raw()[opaque * 4 + 2]);
+    /// # }
+    /// ```
+    pub fn rasterize_into(
+        &self,
+        options: &RasterOptions,
+        buffer: &mut [u8],
+        width: u32,
+        height: u32,
+        stride: usize,
+        format: PixelFormat,
+    ) -> Result<(), RasterError> {
+        // Sizes which overflow could never fit in memory, so they are reported as needing every byte there is
+        let row = (width as usize).checked_mul(4);
+        let required = match height as usize {
+            0 => Some(0),
+            height => stride
+                .checked_mul(height - 1)
+                .zip(row)
+                .and_then(|(rows, row)| rows.checked_add(row)),
+        };
+        let (Some(row), Some(required)) = (row, required) else {
+            return Err(RasterError::InvalidBuffer {
+                length: buffer.len(),
+                required: usize::MAX,
+            });
+        };
+        if stride < row || buffer.len() < required {
+            return Err(RasterError::InvalidBuffer {
+                length: buffer.len(),
+                required: required.max(row.saturating_mul(height as usize)),
+            });
+        }
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let prepared = self.prepare(options)?;
+        let transform = prepared.transform();
+        let buffer = &mut buffer[..required];
+        if stride == row {
+            // The buffer has the same layout as a pixmap, so it can be drawn into directly
+            buffer.fill(0);
+            let mut pixmap = resvg::tiny_skia::PixmapMut::from_bytes(buffer, width, height).ok_or(
+                RasterError::InvalidSize {
+                    width: width as f64,
+                    height: height as f64,
+                },
+            )?;
+            prepared.tree.render(transform, &mut pixmap);
+        } else {
+            // The rasterizer only draws into tightly packed pixels, and the space between rows mustn't be drawn over
+            let mut pixmap =
+                resvg::tiny_skia::Pixmap::new(width, height).ok_or(RasterError::InvalidSize {
+                    width: width as f64,
+                    height: height as f64,
+                })?;
+            prepared.tree.render(transform, &mut pixmap.as_mut());
+            for (source, destination) in pixmap
+                .data()
+                .chunks_exact(row)
+                .zip(buffer.chunks_mut(stride))
+            {
+                destination[..row].copy_from_slice(source);
+            }
+        }
+
+        for line in buffer.chunks_mut(stride) {
+            let pixels = &mut line[..row];
+            if !format.is_premultiplied() {
+                demultiply(pixels);
+            }
+            if format.is_bgra() {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rasterizes the render with the given options and encodes it as a PNG image.
+    pub fn to_png_bytes(&self, options: &RasterOptions) -> Result<Vec<u8>, RasterError> {
+        use image::ImageEncoder;
+
+        let image = into_rgba(self.to_pixmap(options)?)?;
+        let mut bytes = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut bytes).write_image(
+            &image,
+            image.width(),
+            image.height(),
+            image::ColorType::Rgba8,
+        )?;
+        Ok(bytes)
+    }
+
+    /// Rasterizes the render with the given options and encodes it as a (lossless) WebP image.
+    pub fn to_webp_bytes(&self, options: &RasterOptions) -> Result<Vec<u8>, RasterError> {
+        let image = into_rgba(self.to_pixmap(options)?)?;
+        let mut bytes = Vec::new();
+        image::codecs::webp::WebPEncoder::new_lossless(&mut bytes).encode(
+            &image,
+            image.width(),
+            image.height(),
+            image::ColorType::Rgba8,
+        )?;
+        Ok(bytes)
+    }
+
+    /// Rasterizes the render with the given options and encodes it as a JPEG image of the given quality (`1..=100`).
+    ///
+    /// Since JPEG images can't be transparent, the render is drawn over the given background color.
+    pub fn to_jpeg_bytes(
+        &self,
+        options: &RasterOptions,
+        background: image::Rgb<u8>,
+        quality: u8,
+    ) -> Result<Vec<u8>, RasterError> {
+        let pixmap = self.to_pixmap(options)?;
+        let [r, g, b] = background.0;
+
+        // Blend each (premultiplied) pixel over the background
+        let mut rgb = Vec::with_capacity(pixmap.pixels().len() * 3);
+        for pixel in pixmap.pixels() {
+            let inverse = 255 - pixel.alpha() as u32;
+            for (channel, background) in [(pixel.red(), r), (pixel.green(), g), (pixel.blue(), b)] {
+                rgb.push((channel as u32 + (background as u32 * inverse + 127) / 255) as u8);
+            }
+        }
+
+        let mut bytes = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, quality).encode(
+            &rgb,
+            pixmap.width(),
+            pixmap.height(),
+            image::ColorType::Rgb8,
+        )?;
+        Ok(bytes)
+    }
+}
+
+/// A parsed render, ready to be drawn, see [`Render::prepare`].
+pub(crate) struct Prepared {
+    tree: resvg::Tree,
+    /// The scale the tree is drawn at.
+    scale: f64,
+    /// The size of the image the tree is drawn into.
+    pub size: resvg::IntSize,
+    /// The distance (in pixels) from the top of the image down to the baseline of the render.
+    pub baseline: Option<f64>,
+}
+
+impl Prepared {
+    fn transform(&self) -> resvg::tiny_skia::Transform {
+        resvg::tiny_skia::Transform::from_scale(self.scale as f32, self.scale as f32)
+    }
+
+    /// Draws the tree into a new pixmap.
+    pub(crate) fn render(&self) -> Result<resvg::tiny_skia::Pixmap, RasterError> {
+        let mut pixmap = resvg::tiny_skia::Pixmap::new(self.size.width(), self.size.height())
+            .ok_or(RasterError::InvalidSize {
+                width: self.size.width() as f64,
+                height: self.size.height() as f64,
+            })?;
+        self.tree.render(self.transform(), &mut pixmap.as_mut());
+        Ok(pixmap)
+    }
+}
+
+/// Converts a (premultiplied) pixmap into a (straight alpha) RGBA image.
+pub(crate) fn into_rgba(pixmap: resvg::tiny_skia::Pixmap) -> Result<image::RgbaImage, RasterError> {
+    let (width, height) = (pixmap.width(), pixmap.height());
+    let mut data = pixmap.take();
+    demultiply(&mut data);
+    // The buffer is always exactly `width * height * 4` bytes long
+    image::RgbaImage::from_raw(width, height, data).ok_or(RasterError::InvalidSize {
+        width: width as f64,
+        height: height as f64,
+    })
+}
+
+/// Converts (premultiplied) RGBA or BGRA pixels into straight alpha in place.
+fn demultiply(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        // Fully transparent and fully opaque pixels are the same in both representations
+        if alpha != 0 && alpha != 255 {
+            for channel in &mut pixel[..3] {
+                *channel = ((*channel as u32 * 255 + alpha / 2) / alpha) as u8;
+            }
+        }
+    }
+}
+
+/// Returns the size (in pixels) of an image with the given intrinsic size, scaled by the given factor to meet the target size.
+///
+/// Any dimension fixed by the target is used exactly, since scaling the intrinsic size can be off by a fraction of a pixel.
+fn scaled_size(width: f64, height: f64, scale: f64, target: RasterSize) -> (u32, u32) {
+    let scaled = (
+        (width * scale).ceil() as u32,
+        (height * scale).ceil() as u32,
+    );
+    match target {
+        RasterSize::Width(width) => (width, scaled.1),
+        RasterSize::Height(height) => (scaled.0, height),
+        RasterSize::Fit { width, height } => (scaled.0.min(width), scaled.1.min(height)),
+        RasterSize::Scale(_) | RasterSize::FontSize { .. } => scaled,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Intrinsic sizes (in pixels) of a few typical renders.
+    const SIZES: [(f64, f64); 4] = [
+        (36.512, 17.712),
+        (73.904, 35.248),
+        (9.336, 6.992),
+        (312.4, 48.88),
+    ];
+
+    /// A render whose left half is opaque red and right half transparent.
+    fn half_red() -> Render {
+        Render::new(String::from(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="4ex" height="2ex" viewBox="0 0 1768 884"><rect width="884" height="884" fill="red"/></svg>"#,
+        ))
+    }
+
+    /// Returns the left and right pixels in the middle row of an image.
+    fn middle(image: &image::RgbaImage) -> [[u8; 4]; 2] {
+        let y = image.height() / 2;
+        [0, image.width() - 1].map(|x| image.get_pixel(x, y).0)
+    }
+
+    #[test]
+    fn demultiply_pixels() {
+        let mut pixels = [0, 0, 0, 0, 255, 128, 0, 255, 64, 32, 0, 128, 1, 0, 0, 2];
+        demultiply(&mut pixels);
+        assert_eq!(
+            pixels,
+            [0, 0, 0, 0, 255, 128, 0, 255, 128, 64, 0, 128, 128, 0, 0, 2]
+        );
+    }
+
+    #[test]
+    fn encodings() {
+        let render = half_red();
+        let options = RasterOptions {
+            size: RasterSize::Scale(4.0),
+            ..Default::default()
+        };
+        let size = render.raster_size(&options).unwrap();
+
+        let png = image::load_from_memory(&render.to_png_bytes(&options).unwrap()).unwrap();
+        let png = png.into_rgba8();
+        assert_eq!(png.dimensions(), size);
+        assert_eq!(middle(&png), [[255, 0, 0, 255], [0, 0, 0, 0]]);
+
+        // WebP is lossless, so it's the same image as the PNG
+        let webp = image::load_from_memory(&render.to_webp_bytes(&options).unwrap()).unwrap();
+        assert!(webp.into_rgba8() == png);
+
+        // JPEG is lossy, and has no transparency so the background shows through
+        let jpeg = render
+            .to_jpeg_bytes(&options, image::Rgb([0, 0, 255]), 100)
+            .unwrap();
+        let jpeg = image::load_from_memory(&jpeg).unwrap().into_rgba8();
+        assert_eq!(jpeg.dimensions(), size);
+        for (pixel, expected) in middle(&jpeg).into_iter().zip([[255, 0, 0], [0, 0, 255]]) {
+            assert!(
+                pixel.iter().zip(expected).all(|(&a, b)| a.abs_diff(b) < 8),
+                "{pixel:?} is not {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn padded_stride() {
+        let render = half_red();
+        let options = RasterOptions::default();
+        let (width, height) = render.raster_size(&options).unwrap();
+        let row = width as usize * 4;
+
+        let mut tight = vec![0; row * height as usize];
+        render
+            .rasterize_into(&options, &mut tight, width, height, row, PixelFormat::Rgba)
+            .unwrap();
+
+        // The padding at the end of each row is left as it is
+        let stride = row + 12;
+        let mut padded = vec![0xAB; stride * height as usize];
+        render
+            .rasterize_into(
+                &options,
+                &mut padded,
+                width,
+                height,
+                stride,
+                PixelFormat::Rgba,
+            )
+            .unwrap();
+        for (line, expected) in padded.chunks(stride).zip(tight.chunks(row)) {
+            assert_eq!(&line[..row], expected);
+            assert!(line[row..].iter().all(|&byte| byte == 0xAB));
+        }
+        assert_eq!(&tight[..4], [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn invalid_buffers() {
+        let render = half_red();
+        let options = RasterOptions::default();
+        let rasterize = |buffer: &mut [u8], width, height, stride| {
+            render.rasterize_into(&options, buffer, width, height, stride, PixelFormat::Rgba)
+        };
+        let required = |result| match result {
+            Err(RasterError::InvalidBuffer { required, .. }) => required,
+            result => panic!("unexpected result: {result:?}"),
+        };
+
+        assert_eq!(required(rasterize(&mut [0; 16], 2, 3, 8)), 24);
+        // The stride is shorter than a row
+        assert_eq!(required(rasterize(&mut [0; 64], 4, 2, 8)), 32);
+        // Sizes which overflow are rejected rather than wrapping around
+        assert_eq!(
+            required(rasterize(&mut [0; 64], 1, 3, usize::MAX / 2 + 1)),
+            usize::MAX
+        );
+        assert_eq!(
+            required(rasterize(&mut [0; 64], u32::MAX, u32::MAX, usize::MAX)),
+            usize::MAX
+        );
+        assert!(rasterize(&mut [], 0, 0, 0).is_ok());
+    }
+
+    #[test]
+    fn exact_width() {
+        for (width, height) in SIZES {
+            for target in 1..=15000 {
+                let scale = target as f64 / width;
+                let size = scaled_size(width, height, scale, RasterSize::Width(target));
+                assert_eq!(size.0, target, "{width}x{height} at {target}");
+                assert_eq!(size.1, (height * scale).ceil() as u32);
+            }
+        }
+    }
+
+    #[test]
+    fn exact_height() {
+        for (width, height) in SIZES {
+            for target in 1..=15000 {
+                let scale = target as f64 / height;
+                let size = scaled_size(width, height, scale, RasterSize::Height(target));
+                assert_eq!(size.1, target, "{width}x{height} at {target}");
+                assert_eq!(size.0, (width * scale).ceil() as u32);
+            }
+        }
+    }
+
+    #[test]
+    fn fit_within_bounds() {
+        for (width, height) in SIZES {
+            for bound in (1..=4000).step_by(7) {
+                let target = RasterSize::Fit {
+                    width: bound,
+                    height: bound / 2 + 1,
+                };
+                let scale = f64::min(bound as f64 / width, (bound / 2 + 1) as f64 / height);
+                let size = scaled_size(width, height, scale, target);
+                assert!(size.0 <= bound && size.1 <= bound / 2 + 1);
+                // One of the dimensions is always filled exactly
+                assert!(
+                    size.0 == bound || size.1 == bound / 2 + 1,
+                    "{width}x{height} in {bound}"
+                );
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
 const CDN: &str = "https://cdn.jsdelivr.net/npm/mathjax@3/es5/tex-svg-full.js";
+/// The fonts included in the [`CDN`] bundle.
+const FONTS: &[&str] = &["tex"];
 
 pub struct Browser {
     instance: headless_chrome::Browser,
@@ -10,7 +12,11 @@ impl Browser {
         Ok(Browser { instance })
     }
 
-    pub fn render(&self, expression: &str) -> Result<super::Render, crate::RenderError> {
+    pub fn fonts(&self) -> Vec<crate::Font> {
+        FONTS.iter().map(|&name| crate::Font::new(name)).collect()
+    }
+
+    pub fn render(&self, request: &super::Request) -> Result<super::Response, crate::RenderError> {
         let browser = &self.instance;
 
         let tab = browser.new_tab()?;
@@ -20,16 +26,46 @@ impl Browser {
             .find_element("body")?;
 
         // Load MathJax and render
-        let svg = body
+        let response = body
             .call_js_fn(
-                &r#"(expression) => {
+                &r#"(request) => {
+            <COMMON>
+
+            request = JSON.parse(request);
+
             return new Promise((resolve, reject) => {
                 window.MathJax = {
+                    loader: {
+                        // The speech rule engine is loaded on demand from the CDN
+                        load: request.options.speech !== "off" ? ["a11y/sre"] : [],
+                    },
                     startup: {
-                        ready: () => {
+                        ready: async () => {
                             MathJax.startup.defaultReady();
-                            const svg = window.MathJax.tex2svg(expression).children[0].outerHTML;
-                            resolve(svg);
+                            let speech = null;
+                            if (request.options.speech !== "off") {
+                                const Sre = MathJax._.a11y.sre.Sre;
+                                await Sre.setupEngine({ domain: request.options.speech, modality: "speech", locale: "en" });
+                                await Sre.sreReady();
+                                speech = (mathml) => Sre.toSpeech(mathml);
+                            }
+                            const lib = {
+                                mathjax: MathJax._.mathjax.mathjax,
+                                TeX: MathJax.startup.constructors.tex,
+                                SVG: MathJax.startup.constructors.svg,
+                                packages: MathJax.config.tex.packages,
+                                Package: MathJax._.components.package.Package,
+                                adaptor: MathJax.startup.adaptor,
+                                STATE: MathJax._.core.MathItem.STATE,
+                                font: (name) => { throw new Error("font not available: " + name); },
+                                toMathML: (node) => MathJax.startup.toMML(node),
+                                speech,
+                            };
+                            try {
+                                resolve(JSON.stringify(renderAll(lib, request)));
+                            } catch (err) {
+                                reject(err);
+                            }
                         }
                     }
                 };
@@ -43,14 +79,15 @@ impl Browser {
                 document.head.appendChild(mj);
             });
         }"#
+                .replace("<COMMON>", super::COMMON_SRC)
                 .replace("<CDN>", CDN),
-                vec![expression.into()],
+                vec![serde_json::to_string(request)?.into()],
                 true,
             )?
             .value
             .and_then(|value| value.as_str().map(ToString::to_string))
             .unwrap_or_default();
 
-        Ok(super::Render::new(svg))
+        Ok(serde_json::from_str(&response)?)
     }
 }
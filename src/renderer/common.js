@@ -0,0 +1,450 @@
+// Rendering logic shared between all backends.
+// Each backend is expected to provide a `lib` object containing the MathJax classes:
+//  `{ mathjax, TeX, SVG, packages, Package, adaptor, STATE, font, toMathML, speech }` (where `Package` is the class of loadable components, `font(name)` returns the font class with the given name,
+//  `toMathML(node)` serializes an internal MathML tree, and `speech(mathml)` returns the spoken text of some MathML, or is `null` if unavailable)
+//  along with a request of the form `{ expressions: [...], options: {...}, output: "svg" | "mathml" | "errors" }` (see `src/options.rs` and `src/renderer/mod.rs`).
+
+// Returns the offset (in UTF-16 code units) into the expression of the given position within the string of a parser.
+// Each argument (e.g. of `\frac`) is parsed by its own parser, which is found within the string of the enclosing parser.
+function sourceOffset(jax, parser, position) {
+    // The innermost parser is first
+    const parsers = jax.parseOptions.parsers;
+    const index = parsers.indexOf(parser);
+    const chain = index < 0 ? [parser] : parsers.slice(index).reverse();
+
+    let base = 0;
+    let offset = chain.length === 1 ? position : chain[0].i;
+    for (let k = 1; k < chain.length; k++) {
+        const [outer, inner] = [chain[k - 1], chain[k]];
+        const start = outer.string.lastIndexOf(inner.string, outer.i - inner.string.length);
+        if (start < 0) {
+            break;
+        }
+        base += start;
+        offset = base + (k === chain.length - 1 ? position : inner.i);
+    }
+
+    // Macros are expanded (and digits after a `^` separated) by rewriting the rest of the string,
+    //  so the start of the string is the start of the expression up until the first change, and the end is still the end of the expression
+    const string = chain[0].string;
+    if (string !== jax.latex) {
+        let unchanged = 0;
+        while (unchanged < string.length && string[unchanged] === jax.latex[unchanged]) {
+            unchanged++;
+        }
+        if (offset > unchanged) {
+            offset = jax.latex.length - (string.length - offset);
+        }
+    }
+    return Math.max(0, Math.min(offset, jax.latex.length));
+}
+
+// Returns the offset (in UTF-16 code units) into the expression of the parser which failed
+function errorOffset(jax) {
+    const parsers = jax.parseOptions.parsers;
+    return parsers.length === 0 ? jax.latex.length : sourceOffset(jax, parsers[0], parsers[0].i);
+}
+
+// Returns the length in bytes of the UTF-8 encoding of the start of a string, up to the given offset (in UTF-16 code units)
+function utf8Offset(string, offset) {
+    let bytes = 0;
+    for (const c of string.slice(0, offset)) {
+        const code = c.codePointAt(0);
+        bytes += code < 0x80 ? 1 : code < 0x800 ? 2 : code < 0x10000 ? 3 : 4;
+    }
+    return bytes;
+}
+
+// Annotates every node created while parsing with a `data-mjx-src="start:end"` attribute,
+//  the span (in UTF-8 bytes) of the expression it was parsed from (see `RenderOptions::source_spans`).
+// Nodes are annotated by the innermost token (character or macro, along with its arguments) which created them.
+function trackSpans(tex) {
+    const created = [];
+    const factory = tex.parseOptions.nodeFactory;
+    const create = factory.create.bind(factory);
+    factory.create = (...args) => {
+        const node = create(...args);
+        created.push(node);
+        return node;
+    };
+
+    const handler = tex.parseOptions.handlers.get("character");
+    const parse = handler.parse.bind(handler);
+    handler.parse = (input) => {
+        const [parser, c] = input;
+        if (!tex.trackSpans) {
+            return parse(input);
+        }
+
+        const mark = created.length;
+        const start = sourceOffset(tex, parser, parser.i - c.length);
+        try {
+            const result = parse(input);
+            const end = sourceOffset(tex, parser, parser.i);
+            const span = utf8Offset(tex.latex, start) + ":" + utf8Offset(tex.latex, end);
+            for (const node of created.slice(mark)) {
+                if (!node.isKind("text")) {
+                    node.attributes.set("data-mjx-src", span);
+                }
+            }
+            return result;
+        } finally {
+            // Nodes created by any tokens within this one have already been annotated
+            created.length = mark;
+        }
+    };
+
+    // Each node covers (at least) the spans of its children, e.g. a superscript also covers its base
+    const widen = (node) => {
+        if (node.isKind("text")) {
+            return null;
+        }
+        const own = node.attributes.getExplicit("data-mjx-src");
+        let span = own ? own.split(":").map(Number) : null;
+        for (const child of node.childNodes) {
+            const inner = child ? widen(child) : null;
+            if (inner !== null) {
+                span = span === null ? inner : [Math.min(span[0], inner[0]), Math.max(span[1], inner[1])];
+            }
+        }
+        if (span !== null) {
+            node.attributes.set("data-mjx-src", span.join(":"));
+        }
+        return span;
+    };
+    tex.postFilters.add(({ data }) => {
+        if (tex.trackSpans) {
+            widen(data.root);
+        }
+    });
+}
+
+function texOptions(lib, options, tags, validate) {
+    const format = options.tagFormat;
+    const fill = (template, key, value) => template.split(key).join(value);
+
+    // When validating, undefined macros and errors must be reported rather than shown in the output,
+    //  and packages aren't loaded on demand (that happens asynchronously, after the problem would be reported)
+    const excluded = validate ? ["noerrors", "noundefined", "autoload"] : [];
+    const packages = lib.packages.filter((name) => !excluded.includes(name) && name !== "require");
+    return {
+        packages: [...packages, "require"],
+        require: requireOptions(lib, packages),
+        // Errors are kept (along with where they occurred) so they can be reported, as well as being shown in the output
+        formatError: (jax, err) => {
+            jax.lastError = { id: err.id, message: err.message.replace(/\n.*/, ""), offset: errorOffset(jax) };
+            return jax.formatError(err);
+        },
+        tags,
+        tagformat: {
+            number: (n) => fill(format.number, "{n}", n.toString()),
+            tag: (tag) => fill(format.tag, "{tag}", tag),
+            id: (id) => fill(format.id, "{id}", id.replace(/\s/g, "_")),
+        },
+    };
+}
+
+// `\require` only accepts the packages which are already loaded (so it never has to load anything),
+//  any other package is reported as not allowed to be loaded
+function requireOptions(lib, packages) {
+    const allow = {};
+    for (const name of packages) {
+        const extension = "[tex]/" + name;
+        // Packages loaded directly (rather than as components) have to be registered, or they would be loaded again
+        if (!lib.Package.packages.has(extension)) {
+            new lib.Package(extension, true);
+        }
+        allow[extension] = true;
+    }
+    return { allow, defaultAllow: false };
+}
+
+function svgOptions(lib, options) {
+    const svg = { fontCache: options.fontCache };
+    // The TeX font is the default, so it is never explicitly loaded
+    if (options.font !== "tex") {
+        const Font = lib.font(options.font);
+        svg.font = new Font();
+    }
+    return svg;
+}
+
+function createDocument(lib, options, tags, validate = false) {
+    const tex = new lib.TeX(texOptions(lib, options, tags, validate));
+    if (options.sourceSpans) {
+        trackSpans(tex);
+    }
+    const svg = new lib.SVG(svgOptions(lib, options));
+    const doc = lib.mathjax.document("", { InputJax: tex, OutputJax: svg });
+
+    // `convert` only returns the output, so keep hold of the last item compiled to get at its internal MathML tree
+    const MathItem = doc.options.MathItem;
+    doc.options.MathItem = class extends MathItem {
+        compile(document) {
+            super.compile(document);
+            doc.lastItem = this;
+        }
+    };
+    return doc;
+}
+
+// Control sequences (without the leading `\`) which a line may be broken before
+const BREAK_BEFORE = new Set([
+    "le", "leq", "ge", "geq", "ne", "neq", "approx", "equiv", "sim", "simeq", "cong", "propto",
+    "to", "rightarrow", "Rightarrow", "longrightarrow", "Longrightarrow", "implies", "iff",
+    "leftrightarrow", "Leftrightarrow", "in", "notin", "subset", "subseteq", "supset", "supseteq",
+    "cdot", "times", "pm", "mp", "div", "cup", "cap", "wedge", "vee", "oplus", "otimes",
+]);
+
+// Splits an expression into the pieces between each possible line break.
+// Only the top level of the expression is considered, so nothing within a group, environment or `\left...\right` is ever broken.
+// Each piece is `{ text, forced }`, where `forced` is set if the piece follows an explicit `\\` or `\newline`.
+function splitExpression(expression) {
+    const pieces = [];
+    let piece = { text: "", forced: false };
+    let depth = 0;
+    let previous = "";
+
+    const breakHere = (forced) => {
+        if (piece.text.trim() !== "") {
+            pieces.push(piece);
+        }
+        piece = { text: "", forced: forced || piece.text.trim() === "" && piece.forced };
+    };
+
+    let i = 0;
+    while (i < expression.length) {
+        const c = expression[i];
+        if (c === "\\") {
+            // A trailing `\` has no name, it is left for MathJax to report as an error
+            const match = expression.slice(i + 1).match(/^(?:[a-zA-Z]+|.)/s);
+            const name = match ? match[0] : "";
+            const control = c + name;
+            i += control.length;
+
+            if (name === "begin" || name === "left") {
+                depth++;
+            } else if (name === "end" || name === "right") {
+                depth--;
+            }
+
+            if (depth === 0 && (name === "\\" || name === "newline")) {
+                // Skip the optional spacing argument, e.g. `\\[2pt]`
+                const spacing = expression.slice(i).match(/^\s*\[[^\]]*\]/);
+                i += spacing ? spacing[0].length : 0;
+                breakHere(true);
+                previous = "";
+                continue;
+            }
+            if (depth === 0 && BREAK_BEFORE.has(name)) {
+                breakHere(false);
+            }
+            piece.text += control;
+            previous = control;
+            continue;
+        }
+
+        if (c === "{") {
+            depth++;
+        } else if (c === "}") {
+            depth--;
+        } else if (depth === 0 && "=<>+-".includes(c) && !["", "^", "_", "=", "<", ">", "+", "-", "(", "["].includes(previous)) {
+            // Operators directly following another operator (or a sub/superscript) are unary, so can't be broken before
+            breakHere(false);
+        }
+
+        piece.text += c;
+        if (c.trim() !== "") {
+            previous = c;
+        }
+        i++;
+    }
+
+    breakHere(false);
+    return pieces;
+}
+
+// Returns the width (in `em`) of the given expression
+function measure(lib, doc, expression) {
+    // Labels must not persist between measurements, otherwise they would be multiply defined
+    doc.inputJax[0].parseOptions.tags.reset();
+    const svg = lib.adaptor.firstChild(doc.convert(expression, { display: true }));
+    const viewBox = lib.adaptor.getAttribute(svg, "viewBox").split(" ");
+    return parseFloat(viewBox[2]) / 1000;
+}
+
+// Breaks the expression into multiple lines according to the `lineBreaks` option
+function breakLines(lib, options, expression) {
+    const { mode, width, indentAlign, indentShift } = options.lineBreaks;
+    if (mode === "off") {
+        return expression;
+    }
+
+    const doc = mode === "automatic" ? createDocument(lib, options, "none") : null;
+    const lines = [];
+    let line = null;
+    for (const piece of splitExpression(expression)) {
+        if (line === null) {
+            line = piece.text;
+        } else if (piece.forced) {
+            lines.push(line);
+            line = piece.text;
+        } else if (doc !== null) {
+            const available = width - (lines.length > 0 ? indentShift : 0);
+            if (measure(lib, doc, line + piece.text) > available) {
+                lines.push(line);
+                line = piece.text;
+            } else {
+                line += piece.text;
+            }
+        } else {
+            line += piece.text;
+        }
+    }
+    if (line !== null) {
+        lines.push(line);
+    }
+
+    if (lines.length < 2) {
+        return expression;
+    }
+
+    const rows = lines.map((line, index) => {
+        // The empty group keeps binary operators at the start of a line binary
+        const text = index === 0 ? line.trim() : "{}" + line.trim();
+        const shift = index > 0 && indentShift !== 0 ? "\\hspace{" + indentShift + "em}" : "";
+        switch (indentAlign) {
+            case "left":
+                return "&" + shift + text;
+            case "right":
+                return shift + text + "&";
+            default:
+                return shift + text;
+        }
+    });
+
+    const environment = indentAlign === "center" ? "gathered" : "aligned";
+    return "\\begin{" + environment + "}" + rows.join("\\\\") + "\\end{" + environment + "}";
+}
+
+// Typesets every piece of math in an HTML page, as the MathJax `tex2svg-page` example does
+function typesetPage(lib, request) {
+    const page = request.page;
+    const tex = new lib.TeX(Object.assign(texOptions(lib, request.options, request.options.tags, false), {
+        inlineMath: page.inlineMath,
+        displayMath: page.displayMath,
+        processEscapes: page.processEscapes,
+        processEnvironments: page.processEnvironments,
+    }));
+    const svg = new lib.SVG(svgOptions(lib, request.options));
+    const doc = lib.mathjax.document(request.expressions[0], {
+        InputJax: tex,
+        OutputJax: svg,
+        skipHtmlTags: page.skipHtmlTags,
+        ignoreHtmlClass: page.ignoreHtmlClass,
+        processHtmlClass: page.processHtmlClass,
+    });
+    doc.render();
+
+    // The stylesheet is only needed if there is some math
+    if (Array.from(doc.math).length === 0) {
+        lib.adaptor.remove(doc.outputJax.svgStyles);
+    }
+    const html = lib.adaptor.doctype(doc.document) + lib.adaptor.outerHTML(lib.adaptor.root(doc.document));
+    return { renders: [html], speech: [], errors: [], labels: {}, defs: {} };
+}
+
+function renderAll(lib, request) {
+    if (request.output === "html") {
+        return typesetPage(lib, request);
+    }
+
+    const doc = createDocument(lib, request.options, request.options.tags, request.output === "errors");
+    const tex = doc.inputJax[0];
+    const tags = tex.parseOptions.tags;
+
+    const speech = [];
+    const errors = [];
+    const convert = (expression, index) => {
+        if (request.output !== "svg") {
+            // The tree as it is parsed, so it is never broken into lines
+            tex.lastError = null;
+            tex.trackSpans = true;
+            const root = doc.convert(expression, { display: request.display[index], end: lib.STATE.COMPILED });
+            errors[index] = tex.lastError;
+            return lib.toMathML(root);
+        }
+
+        const broken = breakLines(lib, request.options, expression);
+        // Spans would be into the expression as it was broken, rather than as it was given
+        tex.trackSpans = broken === expression;
+        const container = doc.convert(broken, { display: request.display[index] });
+
+        if (request.options.speech !== "off") {
+            // Describe the image to screen readers
+            const text = lib.speech(lib.toMathML(doc.lastItem.root));
+            const svg = lib.adaptor.firstChild(container);
+            const title = lib.adaptor.node("title", {}, [lib.adaptor.text(text)], "http://www.w3.org/2000/svg");
+            lib.adaptor.setAttribute(svg, "aria-label", text);
+            lib.adaptor.insert(title, lib.adaptor.firstChild(svg));
+            speech[index] = text;
+        }
+
+        if (request.options.assistiveMml) {
+            // The equivalent of `a11y/assistive-mml` for a standalone SVG: the drawing is hidden from screen readers,
+            //  which read the hidden MathML instead (an element with `role="img"` would hide its children as well)
+            const svg = lib.adaptor.firstChild(container);
+            lib.adaptor.removeAttribute(svg, "role");
+            for (const child of lib.adaptor.childNodes(svg)) {
+                if (lib.adaptor.kind(child) === "g") {
+                    lib.adaptor.setAttribute(child, "aria-hidden", "true");
+                }
+            }
+            const mathml = lib.toMathML(doc.lastItem.root);
+            const hidden = '<foreignObject width="0" height="0" overflow="hidden">' + mathml + "</foreignObject>";
+            return lib.adaptor.innerHTML(container).replace(/<\/svg>$/, hidden + "</svg>");
+        }
+        return lib.adaptor.innerHTML(container);
+    };
+
+    // Expressions which reference a label that was not yet defined when they were converted
+    const redo = [];
+    const renders = request.expressions.map((expression, index) => {
+        const counter = tags.allCounter;
+        const render = convert(expression, index);
+        if (tags.redo) {
+            redo.push({ index, counter });
+        }
+        return render;
+    });
+
+    // Now that every label is known, re-render any forward references.
+    // This mirrors what MathJax does internally when it recompiles an equation.
+    if (redo.length > 0) {
+        const startEquation = tags.startEquation;
+        for (const { index, counter } of redo) {
+            tags.startEquation = function (math) {
+                startEquation.call(this, math);
+                this.refUpdate = true;
+                this.counter = counter;
+            };
+            renders[index] = convert(request.expressions[index], index);
+        }
+        tags.startEquation = startEquation;
+    }
+
+    const labels = {};
+    for (const [name, label] of Object.entries(tags.allLabels)) {
+        labels[name] = { tag: label.tag, id: label.id };
+    }
+
+    // Glyphs shared by every render, these are only collected in the global cache
+    const defs = {};
+    if (request.options.fontCache === "global") {
+        for (const [id, path] of doc.outputJax.fontCache.cache) {
+            defs[id] = path;
+        }
+    }
+
+    return { renders, speech, errors, labels, defs };
+}
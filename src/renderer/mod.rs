@@ -1,8 +1,14 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
 #[cfg(feature = "browser")]
 pub mod browser;
 #[cfg(feature = "node")]
 pub mod node;
 
+/// The rendering logic shared between all backends.
+static COMMON_SRC: &str = include_str!("common.js");
+
 pub enum Renderer {
     #[cfg(feature = "node")]
     Node(node::Node),
@@ -10,39 +16,315 @@ pub enum Renderer {
     Browser(browser::Browser),
 }
 
+/// A request to render a set of expressions, this is passed to the backend as JSON.
+#[derive(serde::Serialize)]
+pub struct Request<'a> {
+    pub expressions: &'a [&'a str],
+    /// Whether each expression is display (rather than inline) math.
+    pub display: &'a [bool],
+    pub options: &'a crate::RenderOptions,
+    pub output: Output,
+    /// How math is found in the page, this is only given for [`Output::Html`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<Page<'a>>,
+}
+
+/// How math is found in an HTML page, see [`crate::DocumentOptions`].
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<'a> {
+    pub inline_math: &'a [(String, String)],
+    pub display_math: &'a [(String, String)],
+    pub process_escapes: bool,
+    pub process_environments: bool,
+    pub skip_html_tags: &'a [&'a str],
+    pub ignore_html_class: &'a str,
+    pub process_html_class: &'a str,
+}
+
+/// What the backend produces for each expression in a [`Request`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Output {
+    /// The rendered SVG.
+    Svg,
+    /// The internal MathML tree, serialized as MathML.
+    MathMl,
+    /// The internal MathML tree (as for [`Output::MathMl`]), along with any error found while parsing.
+    Errors,
+    /// The (single) expression is an HTML page, which is returned with every piece of math in it typeset.
+    Html,
+}
+
+/// The response from the backend for a [`Request`].
+#[derive(serde::Deserialize)]
+pub struct Response {
+    pub renders: Vec<String>,
+    /// The spoken text of each render, this is empty unless speech was requested.
+    #[serde(default)]
+    pub speech: Vec<Option<String>>,
+    /// The error found while parsing each expression, this is empty unless errors were requested.
+    #[serde(default)]
+    pub errors: Vec<Option<TexError>>,
+    pub labels: BTreeMap<String, Label>,
+    pub defs: BTreeMap<String, String>,
+}
+
+/// An error found by MathJax while parsing an expression.
+#[derive(serde::Deserialize)]
+pub struct TexError {
+    pub id: String,
+    pub message: String,
+    /// The offset (in UTF-16 code units) into the expression where the error was found.
+    pub offset: usize,
+}
+
+/// An equation label defined with `\label{...}`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct Label {
+    /// The equation number the label refers to (without the surrounding tag formatting), e.g. `3.1`.
+    pub tag: String,
+    /// The `id` attribute of the labelled equation.
+    pub id: String,
+}
+
+/// The output of rendering several expressions together, see [`MathJax::render_batch`](crate::MathJax::render_batch).
+pub struct Batch {
+    renders: Vec<Render>,
+    labels: BTreeMap<String, Label>,
+    defs: Arc<FontDefs>,
+}
+
+impl Batch {
+    pub(crate) fn new(response: Response) -> Self {
+        let defs = Arc::new(FontDefs {
+            paths: response.defs,
+        });
+        let mut speech = response.speech.into_iter();
+        let renders = response
+            .renders
+            .into_iter()
+            .map(|source| {
+                let mut render = Render::new(source);
+                render.speech = speech.next().flatten();
+                if !defs.is_empty() {
+                    render.defs = Some(Arc::clone(&defs));
+                }
+                render
+            })
+            .collect();
+
+        Batch {
+            renders,
+            labels: response.labels,
+            defs,
+        }
+    }
+
+    /// Returns the renders, in the same order as the expressions they were rendered from.
+    pub fn renders(&self) -> &[Render] {
+        &self.renders
+    }
+
+    /// Converts the batch into the renders, in the same order as the expressions they were rendered from.
+    pub fn into_renders(self) -> Vec<Render> {
+        self.renders
+    }
+
+    /// Returns every label defined (with `\label{...}`) within the batch, keyed by name.
+    pub fn labels(&self) -> &BTreeMap<String, Label> {
+        &self.labels
+    }
+
+    /// Returns the glyph outlines shared by every render in the batch.
+    ///
+    /// This is only populated when rendering with [`FontCache::Global`](crate::FontCache::Global), and is otherwise empty.
+    pub fn font_defs(&self) -> &FontDefs {
+        &self.defs
+    }
+}
+
+/// Glyph outlines shared between renders, see [`FontCache::Global`](crate::FontCache::Global).
+///
+/// The renders reference these outlines by id, so they must be included once in any page the renders are displayed on.
+/// Definitions from several batches can be combined with [`FontDefs::merge`], since each glyph always has the same id.
+///
+/// ```rust
+/// # fn main() {
+/// use mathjax::{FontCache, FontDefs, MathJax, RenderOptions};
+///
+/// let options = RenderOptions {
+///     font_cache: FontCache::Global,
+///     ..Default::default()
+/// };
+///
+/// let renderer = MathJax::new().unwrap();
+/// let mut defs = FontDefs::default();
+/// let mut html = String::new();
+/// for paragraph in [["x^2", "y^2"], ["x + y", "z"]] {
+///     let batch = renderer.render_batch(paragraph, &options).unwrap();
+///     defs.merge(batch.font_defs());
+///     for render in batch.renders() {
+///         html.push_str(render.as_raw());
+///     }
+/// }
+/// html.insert_str(0, &defs.to_svg());
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FontDefs {
+    /// The path data of each glyph, keyed by id.
+    paths: BTreeMap<String, String>,
+}
+
+impl FontDefs {
+    /// Returns the number of glyphs defined.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Returns whether no glyphs are defined.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Adds every glyph from `other` which is not already defined.
+    pub fn merge(&mut self, other: &FontDefs) {
+        for (id, path) in &other.paths {
+            self.paths.entry(id.clone()).or_insert_with(|| path.clone());
+        }
+    }
+
+    /// Returns the glyphs as an SVG `<defs>...</defs>` element.
+    pub fn to_defs(&self) -> String {
+        let mut defs = String::from("<defs>");
+        for (id, path) in &self.paths {
+            defs.push_str(&format!(r#"<path id="{id}" d="{path}"></path>"#));
+        }
+        defs.push_str("</defs>");
+        defs
+    }
+
+    /// Returns the glyphs as a hidden `<svg>...</svg>` element, this should be inlined once per page (before any of the renders).
+    pub fn to_svg(&self) -> String {
+        format!(
+            r#"<svg id="MJX-SVG-global-cache" style="display: none;" xmlns="http://www.w3.org/2000/svg">{}</svg>"#,
+            self.to_defs()
+        )
+    }
+}
+
 /// The output of a renderer, this is the final [MathJax](https://www.mathjax.org/) image.
 pub struct Render {
     /// The actual SVG source that MathJax outputs
+    original: String,
+    /// The SVG source with the style and frame applied, this is what is output
     source: String,
-    /// Whether the text/line color has been set
-    color_set: bool,
+    /// The colors and appearance of the render
+    style: crate::Style,
+    /// The frame drawn around the render
+    frame: crate::Frame,
+    /// The shared glyph outlines the source references, if it was rendered with a global font cache
+    defs: Option<Arc<FontDefs>>,
+    /// The spoken text of the expression, if it was requested
+    speech: Option<String>,
 }
 
 impl Render {
-    fn new(source: String) -> Self {
+    pub(crate) fn new(source: String) -> Self {
         Render {
+            original: source.clone(),
             source,
-            color_set: false,
+            style: crate::Style::default(),
+            frame: crate::Frame::default(),
+            defs: None,
+            speech: None,
+        }
+    }
+
+    /// Regenerates the output from the original source.
+    fn update(&mut self) {
+        let styled = self.style.apply(&self.original);
+        self.source = self.frame.apply(&styled).into_owned();
+    }
+
+    /// Returns the SVG source with any shared glyph outlines it references included, so that it can be used on its own.
+    #[cfg(feature = "svg")]
+    pub(crate) fn standalone_source(&self) -> std::borrow::Cow<'_, str> {
+        match (&self.defs, self.source.find('>')) {
+            (Some(defs), Some(end)) => {
+                let mut source = self.source.clone();
+                source.insert_str(end + 1, &defs.to_defs());
+                std::borrow::Cow::Owned(source)
+            }
+            _ => std::borrow::Cow::Borrowed(&self.source),
         }
     }
 
     /// Sets the text/line color of the rendered image.  
     ///
-    /// Will return `true` if the operation was successful.
-    /// This function can only be called once, subsequent calls will do nothing and return `false`.
+    /// Will return `true` if the operation was successful, or `false` if `color` is not a valid color.
     ///
     /// Accepts any valid CSS [color](https://developer.mozilla.org/en-US/docs/Web/CSS/color_value) value.
+    #[deprecated(note = "use `Render::set_style` instead")]
     pub fn set_color(&mut self, color: &str) -> bool {
-        if !self.color_set {
-            self.source = self.source.replace("currentColor", color);
-            self.color_set = true;
-            true
-        } else {
-            false
+        match color.parse() {
+            Ok(color) => {
+                self.set_style(crate::Style {
+                    color: Some(color),
+                    ..self.style
+                });
+                true
+            }
+            Err(_) => false,
         }
     }
 
+    /// Sets the colors and appearance of the render, replacing any previous style.
+    ///
+    /// Like [`Render::set_frame`], this changes the SVG source and so applies to every output format.
+    pub fn set_style(&mut self, style: crate::Style) {
+        self.style = style;
+        self.update();
+    }
+
+    /// Returns the colors and appearance of the render, see [`Render::set_style`].
+    pub fn style(&self) -> &crate::Style {
+        &self.style
+    }
+
+    /// Draws the given frame (space and a background) around the render, replacing any previous frame.
+    ///
+    /// This changes the SVG source, and so applies to every output format.
+    /// To only frame a rasterized image, use [`RasterOptions::frame`](crate::RasterOptions::frame) instead.
+    pub fn set_frame(&mut self, frame: crate::Frame) {
+        self.frame = frame;
+        self.update();
+    }
+
+    /// Returns the frame drawn around the render, see [`Render::set_frame`].
+    pub fn frame(&self) -> &crate::Frame {
+        &self.frame
+    }
+
+    /// Returns the layout metrics of the render (its size and baseline).
+    ///
+    /// This will only be `None` if the SVG was not produced by MathJax (and so is missing the expected attributes).
+    pub fn metrics(&self) -> Option<crate::Metrics> {
+        crate::Metrics::parse(&self.source)
+    }
+
+    /// Returns the spoken text of the expression (e.g. "x squared"), this is also the `aria-label` of the SVG.
+    ///
+    /// This is only `Some` when rendered with [`RenderOptions::speech`](crate::RenderOptions::speech) enabled.
+    pub fn speech(&self) -> Option<&str> {
+        self.speech.as_deref()
+    }
+
     /// Returns the underlying SVG string. This is an `<svg>...</svg>` element.
+    ///
+    /// If this was rendered with [`FontCache::Global`](crate::FontCache::Global),
+    /// the glyph outlines are not included (see [`Batch::font_defs`]).
     pub fn as_raw(&self) -> &str {
         &self.source
     }
@@ -52,20 +334,28 @@ impl Render {
         self.source
     }
 
-    /// Converts the render into a [`resvg::Tree`].
+    /// Converts the render into a [`resvg::Tree`], any text is drawn with the system fonts (see [`FontDatabase::System`](crate::FontDatabase::System)).
     #[cfg(feature = "image")]
-    pub fn into_svg(self) -> Result<resvg::Tree, resvg::usvg::Error> {
-        use resvg::usvg::{TreeParsing, TreeTextToPath};
-
-        let opt = resvg::usvg::Options::default();
+    pub fn into_svg(self) -> Result<resvg::Tree, crate::RasterError> {
+        self.to_svg(&crate::FontDatabase::System)
+    }
 
-        let mut fontdb = resvg::usvg::fontdb::Database::new();
-        fontdb.load_system_fonts();
+    /// Converts the render into a [`resvg::Tree`], any text is drawn with the given fonts.
+    #[cfg(feature = "image")]
+    pub fn into_svg_with(
+        self,
+        fonts: &crate::FontDatabase,
+    ) -> Result<resvg::Tree, crate::RasterError> {
+        self.to_svg(fonts)
+    }
 
-        let mut tree = resvg::usvg::Tree::from_data(self.source.as_bytes(), &opt)?;
-        tree.convert_text(&fontdb);
-        let rtree = resvg::Tree::from_usvg(&tree);
-        Ok(rtree)
+    /// Parses the render into a [`resvg::Tree`].
+    #[cfg(feature = "image")]
+    pub(crate) fn to_svg(
+        &self,
+        fonts: &crate::FontDatabase,
+    ) -> Result<resvg::Tree, crate::RasterError> {
+        Ok(parse_svg(&self.standalone_source(), fonts)?)
     }
 
     /// Converts the render into an [`image::DynamicImage`].
@@ -73,29 +363,42 @@ impl Render {
     /// The `scaling_factor` parameter determines how much to scale up the output render (since the underlying SVG is a vector).  
     /// In general, a larger scaling factor = a higher resolution image.  
     /// You'll probably need some trial and error to figure out what works best for your use case, I'd recommend starting at ~`10.0`.  
+    ///
+    /// To produce an image of a predictable size (e.g. to match the surrounding text), use [`Render::rasterize`] instead.
     #[cfg(feature = "image")]
-    pub fn into_image(self, scaling_factor: f32) -> Result<image::DynamicImage, image::ImageError> {
-        let rtree = self.into_svg().map_err(|_| {
-            image::ImageError::Unsupported(image::error::UnsupportedError::from_format_and_kind(
-                image::error::ImageFormatHint::Unknown,
-                image::error::UnsupportedErrorKind::Format(image::error::ImageFormatHint::Unknown),
-            ))
-        })?;
-
-        let pixmap_size = resvg::IntSize::from_usvg(rtree.size)
-            .scale_by(scaling_factor.into())
-            // unwrap is safe as this can only error if either width=0 or height=0, and from_usvg has a minimum value of `1`
-            .unwrap();
-
-        // unwrap is safe as this can only error if either pixmap_size.width=0 or pixmap_size.height=0,
-        //  and since from_usvg must be >=1, the post-scale size must also be >=0
-        let mut pixmap =
-            resvg::tiny_skia::Pixmap::new(pixmap_size.width(), pixmap_size.height()).unwrap();
-        rtree.render(
-            resvg::tiny_skia::Transform::from_scale(scaling_factor, scaling_factor),
-            &mut pixmap.as_mut(),
-        );
-
-        image::load_from_memory_with_format(&pixmap.encode_png().unwrap(), image::ImageFormat::Png)
+    pub fn into_image(
+        self,
+        scaling_factor: f32,
+    ) -> Result<image::DynamicImage, crate::RasterError> {
+        self.rasterize(&crate::RasterOptions {
+            size: crate::RasterSize::Scale(scaling_factor),
+            ..Default::default()
+        })
     }
 }
+
+/// Parses an SVG source into a [`resvg::Tree`].
+#[cfg(feature = "image")]
+pub(crate) fn parse_svg(
+    source: &str,
+    fonts: &crate::FontDatabase,
+) -> Result<resvg::Tree, resvg::usvg::Error> {
+    let tree = parse_usvg(source, fonts)?;
+    let rtree = resvg::Tree::from_usvg(&tree);
+    Ok(rtree)
+}
+
+/// Parses an SVG source into a [`resvg::usvg::Tree`], with any text converted into paths.
+#[cfg(feature = "svg")]
+pub(crate) fn parse_usvg(
+    source: &str,
+    fonts: &crate::FontDatabase,
+) -> Result<resvg::usvg::Tree, resvg::usvg::Error> {
+    use resvg::usvg::{TreeParsing, TreeTextToPath};
+
+    let opt = resvg::usvg::Options::default();
+
+    let mut tree = resvg::usvg::Tree::from_data(source.as_bytes(), &opt)?;
+    tree.convert_text(fonts.database());
+    Ok(tree)
+}
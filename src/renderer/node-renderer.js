@@ -1,21 +1,31 @@
-// Script designed to run as `node -e "SCRIPT_SOURCE" <expression>`,
-//  prints the MathJax render of the given expression to stdout
+// Script designed to run as `node -e "SCRIPT_SOURCE"` with a JSON request on stdin,
+//  prints the JSON response of the render to stdout (this is appended to `common.js`)
 
 // var document = undefined;
 
+const fs = require("fs");
 const { mathjax } = require("./mathjax");
 const { TeX } = require("./input/tex");
 const { SVG } = require("./output/svg");
 const { liteAdaptor } = require("./adaptors/liteAdaptor");
 const { RegisterHTMLHandler } = require("./handlers/html");
 const { AllPackages } = require("./input/tex/AllPackages");
+// Registers the `require` package, which isn't one of `AllPackages`
+require("./input/tex/require/RequireConfiguration");
+const { Package } = require("./components/package");
+const { STATE } = require("./core/MathItem");
+const { SerializedMmlVisitor } = require("./core/MmlTree/SerializedMmlVisitor");
 
-const expression = process.argv[1];
+const request = JSON.parse(fs.readFileSync(0, "utf8"));
 const adaptor = liteAdaptor();
 RegisterHTMLHandler(adaptor);
-const tex = new TeX({ packages: AllPackages });
-const svg = new SVG({ fontCache: "local" });
-const doc = mathjax.document("", { InputJax: tex, OutputJax: svg });
-const node = doc.convert(expression);
-const element = adaptor.innerHTML(node);
-console.log(element);
+
+// Each font module exports a single font class
+const font = (name) => Object.values(require("./output/svg/fonts/" + name)).find((value) => typeof value === "function");
+
+const visitor = new SerializedMmlVisitor();
+const toMathML = (node) => visitor.visitTree(node);
+
+// The speech rule engine isn't bundled, so speech is never requested (see `node.rs`)
+const lib = { mathjax, TeX, SVG, packages: AllPackages, Package, adaptor, STATE, font, toMathML, speech: null };
+console.log(JSON.stringify(renderAll(lib, request)));
@@ -1,4 +1,5 @@
-use std::process::Command;
+use std::io::Write;
+use std::process::{Command, Stdio};
 
 static MATHJAX: &[u8] = include_bytes!("../../mathjax-data/data.zip");
 static RENDERER_SRC: &str = include_str!("node-renderer.js");
@@ -24,25 +25,58 @@ pub fn available() -> bool {
 pub struct Node {
     /// The location of the MathJax library source files.
     mathjax_lib: tempfile::TempDir,
+    /// The fonts available in the MathJax library.
+    fonts: Vec<crate::Font>,
 }
 
 impl Node {
     pub fn create() -> Result<Self, crate::InitError> {
         let mathjax_lib = tempfile::tempdir()?;
         zip_extract::extract(std::io::Cursor::new(MATHJAX), mathjax_lib.path(), true)?;
-        Ok(Node { mathjax_lib })
+
+        // Each font is a module within `output/svg/fonts`
+        let mut fonts = Vec::new();
+        for entry in std::fs::read_dir(mathjax_lib.path().join("output/svg/fonts"))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "js") {
+                if let Some(name) = path.file_stem() {
+                    fonts.push(crate::Font::new(name.to_string_lossy()));
+                }
+            }
+        }
+
+        Ok(Node { mathjax_lib, fonts })
+    }
+
+    pub fn fonts(&self) -> Vec<crate::Font> {
+        self.fonts.clone()
     }
 
-    pub fn render(&self, expression: &str) -> Result<super::Render, crate::RenderError> {
-        let cmd = Command::new("node")
-            .args(["-e", RENDERER_SRC, expression])
+    pub fn render(&self, request: &super::Request) -> Result<super::Response, crate::RenderError> {
+        // The bundled `a11y/sre` module depends on the `speech-rule-engine` package, which isn't included
+        if request.options.speech != crate::Speech::Off {
+            return Err(crate::RenderError::SpeechUnavailable);
+        }
+
+        let script = format!("{}\n{}", super::COMMON_SRC, RENDERER_SRC);
+        let mut child = Command::new("node")
+            .args(["-e", &script])
             .current_dir(self.mathjax_lib.path())
-            .output()?;
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // The script reads the entire request before writing anything, so this can't deadlock
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&serde_json::to_vec(request)?)?;
+        }
+
+        let cmd = child.wait_with_output()?;
         let stderr = String::from_utf8_lossy(&cmd.stderr).to_string();
 
         if stderr.is_empty() {
-            let svg = String::from_utf8_lossy(&cmd.stdout).to_string();
-            Ok(super::Render::new(svg))
+            Ok(serde_json::from_slice(&cmd.stdout)?)
         } else {
             Err(crate::RenderError::MathJaxError(stderr))
         }
@@ -0,0 +1,115 @@
+use crate::frame::set_attribute;
+use crate::Render;
+use resvg::usvg::{self, NodeExt};
+use std::ops::Range;
+
+/// The prefix of the `id` given to each annotated element, so it can be found once the SVG is parsed.
+const ID_PREFIX: &str = "mjx-src:";
+
+impl Render {
+    /// Returns the span of the expression (as byte offsets) which produced the part of the render at the given point,
+    /// this requires the expression to have been rendered with [`RenderOptions::source_spans`](crate::RenderOptions::source_spans).
+    ///
+    /// The point is in `em` (multiples of the font size) from the top left corner of the render.
+    /// When several parts of the render overlap the point, the smallest (and so most specific) is chosen.
+    /// Returns `None` if nothing was drawn at the point, or the render has no fixed coordinate system (e.g. it has a tag).
+    /// Any text (e.g. from `\text{...}`) is laid out with the system fonts, see [`Render::source_at_with`] to use other fonts.
+    ///
+    /// ```rust
+    /// # fn main() {
+    /// use mathjax::{MathJax, RenderOptions};
+    ///
+    /// let options = RenderOptions {
+    ///     source_spans: true,
+    ///     ..Default::default()
+    /// };
+    /// let renderer = MathJax::new().unwrap();
+    /// let expression = r#"x + \frac{1}{y}"#;
+    /// let render = renderer.render_with(expression, &options).unwrap();
+    ///
+    /// // Just above the baseline, at the left edge
+    /// let baseline = render.metrics().unwrap().em().height;
+    /// let span = render.source_at(0.25, baseline - 0.2).unwrap();
+    /// assert_eq!(&expression[span], "x");
+    ///
+    /// // Just above the baseline, at the right edge
+    /// let width = render.metrics().unwrap().em().width;
+    /// let span = render.source_at(width - 0.25, baseline - 0.2).unwrap();
+    /// assert_eq!(&expression[span], r#"\frac{1}{y}"#);
+    /// # }
+    /// ```
+    pub fn source_at(&self, x: f64, y: f64) -> Option<Range<usize>> {
+        self.source_at_with(x, y, &crate::FontDatabase::System)
+    }
+
+    /// Returns the span of the expression which produced the part of the render at the given point (as [`Render::source_at`]),
+    /// any text is laid out with the given fonts.
+    pub fn source_at_with(
+        &self,
+        x: f64,
+        y: f64,
+        fonts: &crate::FontDatabase,
+    ) -> Option<Range<usize>> {
+        let view_box = self.metrics()?.view_box()?;
+
+        // Attributes other than `id` are lost once parsed, so each span is copied into the `id` of its element
+        let source = self.standalone_source();
+        let mut annotated = String::with_capacity(source.len());
+        let mut rest = &source[..];
+        while let Some(start) = rest.find("<g ") {
+            let end = rest[start..]
+                .find('>')
+                .map_or(rest.len(), |end| start + end);
+            let mut tag = rest[start..end].to_string();
+            if let Some(span) = attribute(&tag, "data-mjx-src") {
+                let id = format!("{ID_PREFIX}{span}");
+                set_attribute(&mut tag, "id", &id);
+            }
+            annotated.push_str(&rest[..start]);
+            annotated.push_str(&tag);
+            rest = &rest[end..];
+        }
+        annotated.push_str(rest);
+
+        let tree = crate::renderer::parse_usvg(&annotated, fonts).ok()?;
+        // The tree is in the coordinates of the view box, which is in thousandths of an `em`
+        let (x, y) = (view_box.x + x * 1000.0, view_box.y + y * 1000.0);
+
+        let mut found: Option<(f64, Range<usize>)> = None;
+        for node in tree.root.descendants() {
+            let usvg::NodeKind::Group(ref group) = *node.borrow() else {
+                continue;
+            };
+            let Some((start, end)) = group
+                .id
+                .strip_prefix(ID_PREFIX)
+                .and_then(|span| span.split_once(':'))
+            else {
+                continue;
+            };
+            let (Ok(start), Ok(end)) = (start.parse(), end.parse()) else {
+                continue;
+            };
+            let Some(bbox) = node.calculate_bbox() else {
+                continue;
+            };
+
+            let area = bbox.width() * bbox.height();
+            let inside =
+                (bbox.x()..=bbox.right()).contains(&x) && (bbox.y()..=bbox.bottom()).contains(&y);
+            if inside && found.as_ref().is_none_or(|(smallest, _)| area < *smallest) {
+                found = Some((area, start..end));
+            }
+        }
+
+        found.map(|(_, span)| span)
+    }
+}
+
+/// Returns the value of an attribute of the given (opening) tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let prefix = format!(" {name}=\"");
+    let start = tag.find(&prefix)? + prefix.len();
+    let end = tag[start..].find('"')?;
+    Some(&tag[start..start + end])
+}
@@ -0,0 +1,292 @@
+use crate::frame::{set_attribute, set_style};
+use crate::metrics::format_number;
+use crate::{Color, Metrics};
+use std::borrow::Cow;
+
+/// The colors and appearance of a render, see [`Render::set_style`](crate::Render::set_style).
+///
+/// A style can be changed as many times as needed, each time it replaces the previous style rather than being applied on top of it.
+/// Colors set within the expression itself (e.g. with `\color{red}{...}`) always take precedence over the foreground color:
+/// ```rust
+/// # fn main() {
+/// use mathjax::{Color, MathJax, Style};
+///
+/// let renderer = MathJax::new().unwrap();
+/// let mut render = renderer.render(r#"x + \color{red}{y}"#).unwrap();
+/// render.set_style(Style {
+///     color: Some(Color::rgb(0, 0, 255)),
+///     ..Default::default()
+/// });
+///
+/// let image = render.into_image(5.0).unwrap().into_rgba8();
+/// let opaque = |[red, green, blue, alpha]: [u8; 4]| (alpha == 255).then_some([red, green, blue]);
+/// let colors: Vec<_> = image.pixels().filter_map(|pixel| opaque(pixel.0)).collect();
+/// assert!(colors.contains(&[0, 0, 255]));
+/// assert!(colors.contains(&[255, 0, 0]));
+/// assert!(!colors.contains(&[0, 0, 0]));
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    /// The foreground (text and line) color.
+    ///
+    /// Defaults to `None`, which inherits the CSS `color` of the surrounding page (or black when used on its own).
+    pub color: Option<Color>,
+    /// The color the area behind the render is filled with.
+    /// Unlike [`Frame::background`](crate::Frame::background), this only covers the render itself and not any padding around it.
+    ///
+    /// Defaults to `None`, which leaves the background transparent.
+    pub background: Option<Color>,
+    /// The opacity of the foreground, between `0.0` (invisible) and `1.0` (opaque).
+    ///
+    /// Values outside of this range are clamped to it, and `NaN` is treated as `1.0`.
+    /// Defaults to `1.0`.
+    pub opacity: f64,
+    /// Extra thickness (in `em`) added to rules, such as fraction bars and the tops of square roots.
+    ///
+    /// Each rule grows equally on both sides, and its length is unchanged.
+    /// A negative value makes rules thinner (down to nothing), and `NaN` is treated as `0.0`.
+    /// Defaults to `0.0`, MathJax draws rules `0.06em` thick.
+    pub rule_thickness: f64,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            color: None,
+            background: None,
+            opacity: 1.0,
+            rule_thickness: 0.0,
+        }
+    }
+}
+
+impl Style {
+    /// Returns whether the style leaves a render unchanged.
+    pub fn is_empty(&self) -> bool {
+        *self == Style::default()
+    }
+
+    /// Applies the style to the given MathJax SVG.
+    pub(crate) fn apply<'a>(&self, source: &'a str) -> Cow<'a, str> {
+        let Some(end) = source.find('>').filter(|_| !self.is_empty()) else {
+            return Cow::Borrowed(source);
+        };
+
+        // Glyphs and rules are drawn in `currentColor`, which is the value of the CSS `color` property
+        let mut tag = source[..end].to_string();
+        if let Some(color) = self.color {
+            set_style(&mut tag, "color", &color.to_string());
+        }
+
+        let mut styled = String::with_capacity(source.len() + 128);
+        styled.push_str(&tag);
+        styled.push('>');
+        if let Some(background) = self.background {
+            // Renders without a fixed coordinate system (e.g. those with a tag) are filled in their entirety
+            let view_box = Metrics::parse(source).and_then(|metrics| metrics.view_box());
+            let (x, y, width, height) = view_box.map_or_else(
+                || {
+                    (
+                        String::from("0"),
+                        String::from("0"),
+                        String::from("100%"),
+                        String::from("100%"),
+                    )
+                },
+                |view_box| {
+                    (
+                        format_number(view_box.x),
+                        format_number(view_box.y),
+                        format_number(view_box.width),
+                        format_number(view_box.height),
+                    )
+                },
+            );
+            styled.push_str(&format!(
+                r#"<rect x="{x}" y="{y}" width="{width}" height="{height}" fill="{}" fill-opacity="{}"></rect>"#,
+                background.hex(),
+                format_number(background.opacity()),
+            ));
+        }
+
+        // The content is within a single group after the glyph definitions (if there are any)
+        let mut rest = &source[end + 1..];
+        if let Some(start) = rest.find("<g ") {
+            styled.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let opacity = match self.opacity {
+                opacity if opacity.is_nan() => 1.0,
+                opacity => opacity.clamp(0.0, 1.0),
+            };
+            if opacity != 1.0 {
+                styled.push_str(&format!(r#"<g opacity="{}""#, format_number(opacity)));
+                rest = &rest[2..];
+            }
+        }
+
+        let thickness = match self.rule_thickness {
+            thickness if thickness.is_nan() => 0.0,
+            thickness => thickness * 1000.0,
+        };
+        if thickness != 0.0 {
+            // Rules are unfilled rects which inherit the foreground color, other rects are backgrounds (with a fill) or borders (with their own stroke width)
+            while let Some(start) = rest.find("<rect ") {
+                let end = rest[start..]
+                    .find('>')
+                    .map_or(rest.len(), |end| start + end);
+                let mut rect = rest[start..end].to_string();
+                if !rect.contains(" fill=\"") && !rect.contains(" stroke-width=\"") {
+                    thicken(&mut rect, thickness);
+                }
+                styled.push_str(&rest[..start]);
+                styled.push_str(&rect);
+                rest = &rest[end..];
+            }
+        }
+        styled.push_str(rest);
+
+        Cow::Owned(styled)
+    }
+}
+
+/// Adds thickness to the rule drawn by the given rect's start tag (without the closing `>`), keeping it centered on the same line.
+///
+/// The thickness of a rule is its smaller dimension, so this is the height of a horizontal rule (e.g. a fraction bar).
+fn thicken(rect: &mut String, thickness: f64) {
+    let value = |name: &str| {
+        let prefix = format!(" {name}=\"");
+        let start = rect.find(&prefix)? + prefix.len();
+        let value = &rect[start..start + rect[start..].find('"')?];
+        value.parse::<f64>().ok()
+    };
+    let (Some(width), Some(height)) = (value("width"), value("height")) else {
+        return;
+    };
+    let (size, position) = if height <= width {
+        (("height", height), ("y", value("y").unwrap_or(0.0)))
+    } else {
+        (("width", width), ("x", value("x").unwrap_or(0.0)))
+    };
+
+    let thickened = (size.1 + thickness).max(0.0);
+    set_attribute(rect, size.0, &format_number(thickened));
+    set_attribute(
+        rect,
+        position.0,
+        &format_number(position.1 - (thickened - size.1) / 2.0),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="2.262ex" height="2.283ex" style="vertical-align: -0.666ex;" viewBox="0 -715 1000 1009"><defs><path id="A" d=""></path></defs><g stroke="currentColor" fill="currentColor" stroke-width="0"><use xlink:href="#A"></use><rect width="1000" height="60" x="0" y="220"></rect><rect width="10" height="10" fill="red"></rect></g></svg>"##;
+
+    #[test]
+    fn empty() {
+        assert!(matches!(
+            Style::default().apply(SOURCE),
+            Cow::Borrowed(SOURCE)
+        ));
+    }
+
+    #[test]
+    fn color() {
+        let style = Style {
+            color: Some(Color::rgb(0, 0, 255)),
+            ..Default::default()
+        };
+        assert_eq!(
+            style.apply(SOURCE),
+            SOURCE.replace("-0.666ex;", "-0.666ex; color: #0000ff;")
+        );
+    }
+
+    #[test]
+    fn background() {
+        let style = Style {
+            background: Some(Color::WHITE),
+            ..Default::default()
+        };
+        let rect = r##"<rect x="0" y="-715" width="1000" height="1009" fill="#ffffff" fill-opacity="1"></rect>"##;
+        assert_eq!(
+            style.apply(SOURCE),
+            SOURCE.replace("<defs>", &format!("{rect}<defs>"))
+        );
+
+        // A render which stretches to fill its container is filled in its entirety
+        let source = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100%" height="2.009ex" style="vertical-align: -0.566ex; min-width: 9.87ex;"><g></g></svg>"#;
+        assert!(style
+            .apply(source)
+            .contains(r#"<rect x="0" y="0" width="100%" height="100%""#));
+    }
+
+    #[test]
+    fn opacity() {
+        let style = Style {
+            opacity: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            style.apply(SOURCE),
+            SOURCE.replace("<g stroke=", r#"<g opacity="0.5" stroke="#)
+        );
+    }
+
+    #[test]
+    fn opacity_is_clamped() {
+        for (opacity, expected) in [(f64::NAN, None), (2.0, None), (-1.0, Some("0"))] {
+            let style = Style {
+                opacity,
+                ..Default::default()
+            };
+            let expected = match expected {
+                Some(opacity) => {
+                    SOURCE.replace("<g stroke=", &format!(r#"<g opacity="{opacity}" stroke="#))
+                }
+                None => SOURCE.to_string(),
+            };
+            assert_eq!(style.apply(SOURCE), expected);
+        }
+    }
+
+    #[test]
+    fn rule_thickness() {
+        let style = Style {
+            rule_thickness: 0.02,
+            ..Default::default()
+        };
+        // Only the rule is thickened (equally on both sides), not the filled rect, and its width is unchanged
+        assert_eq!(
+            style.apply(SOURCE),
+            SOURCE.replace(
+                r#"<rect width="1000" height="60" x="0" y="220">"#,
+                r#"<rect width="1000" height="80" x="0" y="210">"#
+            )
+        );
+
+        // A vertical rule is thickened horizontally
+        let source = SOURCE.replace(
+            r#"<rect width="1000" height="60" x="0" y="220">"#,
+            r#"<rect width="60" height="1000" x="100">"#,
+        );
+        assert_eq!(
+            style.apply(&source),
+            source.replace(
+                r#"<rect width="60" height="1000" x="100">"#,
+                r#"<rect width="80" height="1000" x="90">"#
+            )
+        );
+
+        // Rules can be made thinner, but not inside out
+        let style = Style {
+            rule_thickness: -1.0,
+            ..Default::default()
+        };
+        assert!(style
+            .apply(SOURCE)
+            .contains(r#"<rect width="1000" height="0" x="0" y="250">"#));
+    }
+}
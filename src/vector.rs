@@ -0,0 +1,112 @@
+use crate::Render;
+use resvg::usvg::{self, NodeExt};
+
+/// The number of points (`1/72` of an inch) in a CSS pixel (`1/96` of an inch).
+const POINTS_PER_PIXEL: f64 = 72.0 / 96.0;
+
+/// A render converted into a list of filled and stroked paths, this is what every vector output format is written from.
+///
+/// Coordinates are in points (`1/72` of an inch), with the origin at the top left and the y axis pointing down.
+pub(crate) struct Drawing {
+    pub width: f64,
+    pub height: f64,
+    pub shapes: Vec<Shape>,
+}
+
+/// A single path within a [`Drawing`].
+pub(crate) struct Shape {
+    pub segments: Vec<usvg::PathSegment>,
+    pub fill: Option<Fill>,
+    pub stroke: Option<Stroke>,
+}
+
+pub(crate) struct Fill {
+    pub color: [u8; 3],
+    pub opacity: f64,
+    pub even_odd: bool,
+}
+
+pub(crate) struct Stroke {
+    pub color: [u8; 3],
+    pub opacity: f64,
+    pub width: f64,
+}
+
+impl Render {
+    /// Converts the render into a drawing, sized to match text of the given font size (in points), with any text drawn with the given fonts.
+    pub(crate) fn to_drawing(
+        &self,
+        font_size: f64,
+        fonts: &crate::FontDatabase,
+    ) -> Result<Drawing, crate::RasterError> {
+        let tree = crate::renderer::parse_usvg(&self.standalone_source(), fonts)?;
+
+        // The height of the SVG is always in `ex`, so it can be used to find the size of the font.
+        // Without metrics (e.g. when the render has a tag) the SVG is drawn at its intrinsic size instead.
+        let scale = crate::Metrics::parse(self.as_raw()).map_or(POINTS_PER_PIXEL, |metrics| {
+            metrics.em().total_height() * font_size / tree.size.height()
+        });
+        let mut transform = usvg::Transform::new_scale(scale, scale);
+        transform.append(&usvg::utils::view_box_to_transform(
+            tree.view_box.rect,
+            tree.view_box.aspect,
+            tree.size,
+        ));
+
+        let mut shapes = Vec::new();
+        for node in tree.root.descendants() {
+            let usvg::NodeKind::Path(ref path) = *node.borrow() else {
+                continue;
+            };
+            if path.visibility != usvg::Visibility::Visible {
+                continue;
+            }
+
+            let opacity: f64 = node
+                .ancestors()
+                .filter_map(|ancestor| match *ancestor.borrow() {
+                    usvg::NodeKind::Group(ref group) => Some(group.opacity.get()),
+                    _ => None,
+                })
+                .product();
+            let mut path_transform = transform;
+            path_transform.append(&node.abs_transform());
+
+            // Gradients and patterns are never produced by MathJax, so only solid colors are supported
+            let fill = path.fill.as_ref().and_then(|fill| match fill.paint {
+                usvg::Paint::Color(color) => Some(Fill {
+                    color: [color.red, color.green, color.blue],
+                    opacity: fill.opacity.get() * opacity,
+                    even_odd: fill.rule == usvg::FillRule::EvenOdd,
+                }),
+                _ => None,
+            });
+            let stroke = path.stroke.as_ref().and_then(|stroke| match stroke.paint {
+                usvg::Paint::Color(color) => {
+                    let (scale_x, scale_y) = path_transform.get_scale();
+                    Some(Stroke {
+                        color: [color.red, color.green, color.blue],
+                        opacity: stroke.opacity.get() * opacity,
+                        width: stroke.width.get() * (scale_x * scale_y).sqrt(),
+                    })
+                }
+                _ => None,
+            });
+            if fill.is_none() && stroke.is_none() {
+                continue;
+            }
+
+            shapes.push(Shape {
+                segments: usvg::TransformedPath::new(&path.data, path_transform).collect(),
+                fill,
+                stroke,
+            });
+        }
+
+        Ok(Drawing {
+            width: tree.size.width() * scale,
+            height: tree.size.height() * scale,
+            shapes,
+        })
+    }
+}
@@ -0,0 +1,25 @@
+use mathjax::{LineBreakMode, LineBreaks, MathJax, RenderOptions};
+
+#[test]
+fn trailing_backslash() {
+    let renderer = MathJax::new().unwrap();
+    let expression = r"x = a + b \";
+    let options = |mode| RenderOptions {
+        line_breaks: LineBreaks {
+            mode,
+            width: 5.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // The backslash is left for MathJax to handle, as it is without line breaking
+    let plain = renderer.render(expression).unwrap();
+    let manual = renderer
+        .render_with(expression, &options(LineBreakMode::Manual))
+        .unwrap();
+    assert_eq!(manual.as_raw(), plain.as_raw());
+    renderer
+        .render_with(expression, &options(LineBreakMode::Automatic))
+        .unwrap();
+}
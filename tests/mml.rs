@@ -0,0 +1,15 @@
+use mathjax::{MathJax, MmlElement, MmlKind};
+
+#[test]
+fn non_breaking_spaces() {
+    let renderer = MathJax::new().unwrap();
+    for (expression, text) in [("a~b", "\u{A0}"), (r"\text{ a b }", "\u{A0}a b\u{A0}")] {
+        let math = renderer.to_mml(expression).unwrap();
+        let mtext = math
+            .descendants()
+            .find(|element| element.kind == MmlKind::Mtext)
+            .unwrap();
+        assert_eq!(mtext.text(), text);
+        assert_eq!(math.to_string().parse::<MmlElement>().unwrap(), math);
+    }
+}
@@ -0,0 +1,170 @@
+#![cfg(feature = "server")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+/// The server process, which is killed when dropped.
+struct Server {
+    child: Child,
+    address: String,
+}
+
+impl Server {
+    /// Starts the server on a port chosen by the system.
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_mathjax-server"))
+            .args([
+                "--address",
+                "127.0.0.1:0",
+                "--workers",
+                "1",
+                "--max-length",
+                "64",
+            ])
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stderr.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let address = line
+            .trim()
+            .strip_prefix("listening on http://")
+            .unwrap_or_else(|| panic!("unexpected output: {line}"))
+            .to_string();
+        Server { child, address }
+    }
+
+    /// Sends a `GET` request, returning the status, headers and body of the response.
+    fn get(&self, path: &str, headers: &[(&str, &str)]) -> Response {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        let mut request = format!(
+            "GET {path} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
+            self.address
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        let end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap();
+        let head = std::str::from_utf8(&response[..end]).unwrap();
+        let mut lines = head.split("\r\n");
+        let status = lines
+            .next()
+            .unwrap()
+            .split(' ')
+            .nth(1)
+            .unwrap()
+            .parse()
+            .unwrap();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        Response {
+            status,
+            headers,
+            body: response[end + 4..].to_vec(),
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn text(&self) -> &str {
+        std::str::from_utf8(&self.body).unwrap()
+    }
+}
+
+#[test]
+fn endpoints() {
+    let server = Server::start();
+
+    let health = server.get("/health", &[]);
+    assert_eq!(health.status, 200);
+    assert_eq!(health.text(), "ok\n");
+
+    let svg = server.get("/svg?tex=x%5E2&color=red", &[]);
+    assert_eq!(svg.status, 200);
+    assert_eq!(svg.header("content-type"), Some("image/svg+xml"));
+    assert!(svg.text().starts_with("<svg"));
+    assert!(svg.header("cache-control").is_some());
+
+    let png = server.get("/png?tex=x%5E2&size=24&dpi=144", &[]);
+    assert_eq!(png.status, 200);
+    assert_eq!(png.header("content-type"), Some("image/png"));
+    assert!(png.body.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+    let mathml = server.get("/mathml?tex=x%5E2", &[]);
+    assert_eq!(mathml.status, 200);
+    assert!(mathml.text().starts_with("<math"));
+
+    // Each endpoint (and each set of parameters) has its own tag
+    let etags = [&svg, &png, &mathml].map(|response| response.header("etag").unwrap());
+    assert!(etags
+        .iter()
+        .enumerate()
+        .all(|(i, etag)| !etags[..i].contains(etag)));
+    assert_ne!(
+        server.get("/svg?tex=x%5E2", &[]).header("etag"),
+        Some(etags[0])
+    );
+
+    let metrics = server.get("/metrics", &[]);
+    let renders = |metrics: &Response| {
+        metrics
+            .text()
+            .lines()
+            .find_map(|line| line.strip_prefix("mathjax_render_seconds_count "))
+            .unwrap()
+            .to_string()
+    };
+    assert!(metrics
+        .text()
+        .contains("mathjax_requests_total{endpoint=\"svg\"} 2\n"));
+    assert_eq!(renders(&metrics), "4");
+
+    // A cached render is answered without rendering again
+    let cached = server.get("/svg?tex=x%5E2&color=red", &[("If-None-Match", etags[0])]);
+    assert_eq!(cached.status, 304);
+    assert_eq!(cached.header("etag"), Some(etags[0]));
+    assert!(cached.body.is_empty());
+    let metrics = server.get("/metrics", &[]);
+    assert!(metrics.text().contains("mathjax_not_modified_total 1\n"));
+    assert_eq!(renders(&metrics), "4");
+
+    let long = "x".repeat(65);
+    assert_eq!(server.get(&format!("/svg?tex={long}"), &[]).status, 413);
+    let longer = "x".repeat(1000);
+    assert_eq!(server.get(&format!("/svg?tex={longer}"), &[]).status, 414);
+    assert_eq!(server.get("/svg", &[]).status, 400);
+    assert_eq!(server.get("/unknown", &[]).status, 404);
+}
@@ -0,0 +1,14 @@
+#![cfg(feature = "eps")]
+
+use mathjax::{FontDatabase, MathJax};
+
+#[test]
+fn vector_output_uses_the_given_fonts() {
+    let renderer = MathJax::new().unwrap();
+    // The MathJax fonts have no CJK glyphs, so this is left as text for the font database to draw
+    let render = renderer.render(r#"x\text{漢}"#).unwrap();
+
+    // Without any fonts only the `x` is drawn
+    let eps = render.to_eps_with(12.0, &FontDatabase::Empty).unwrap();
+    assert_eq!(eps.matches("newpath").count(), 1);
+}